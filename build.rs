@@ -0,0 +1,31 @@
+//
+// With the ffi-c-test feature, compiles src/ffi/test.c, the C side of the ffi module's tests,
+// when building for the host, where there is a C compiler to hand. It is only linked by the
+// tests, through #[link] on a #[cfg(ffi_c_test)] extern block, so nothing else gets it.
+//
+// Without the feature there is nothing to do, and cc isn't built at all, so a crate
+// depending on this one doesn't need a C compiler for it.
+//
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "ffi-c-test")]
+    ffi_c_test();
+}
+
+#[cfg(feature = "ffi-c-test")]
+fn ffi_c_test() {
+    println!("cargo:rerun-if-changed=src/ffi/test.c");
+    if std::env::var("TARGET") != std::env::var("HOST") {
+        return;
+    }
+    cc::Build::new()
+        .file("src/ffi/test.c")
+        .cargo_metadata(false)
+        .compile("ffi_test");
+    println!(
+        "cargo:rustc-link-search=native={}",
+        std::env::var("OUT_DIR").unwrap()
+    );
+    println!("cargo:rustc-cfg=ffi_c_test");
+}
@@ -0,0 +1,16 @@
+#!/bin/sh
+#
+# The out_param tests under Miri, which catches reading an out param the callee didn't
+# write on the error path, and an out param with a destructor leaked or dropped twice.
+#
+#   scripts/miri.sh [cargo miri test args]
+#
+# Miri isn't in every nightly, nightly-2022-01-23 from rust-toolchain among them,
+# MIRI_TOOLCHAIN=nightly-YYYY-MM-DD picks one which has it.
+#
+set -e
+cd "$(dirname "$0")/.."
+
+toolchain=${MIRI_TOOLCHAIN:-$(cat rust-toolchain)}
+rustup component add --toolchain "$toolchain" miri rust-src
+cargo "+$toolchain" miri test "$@" out_param
@@ -0,0 +1,38 @@
+#!/bin/sh
+#
+# What Display's string table costs in a --release build, with and without the strip-*
+# features, from the sizes of the strings module's statics in the rlib's symbol table.
+#
+#   scripts/size.sh [cargo build args]
+#
+set -e
+cd "$(dirname "$0")/.."
+
+rlib=${CARGO_TARGET_DIR:-target}/release/liberror_compat_rust_experiments.rlib
+
+# 0 if it isn't there, e.g. SHARED's one entry can end up in description()'s code.
+symbol_size() {
+    # Without a size column if it is empty.
+    size=$(nm -S -C --defined-only "$rlib" 2>/dev/null |
+        awk -v name="error_compat_rust_experiments::strings::$1" 'NF == 4 && $4 == name { print $2; exit }')
+    echo $((0x${size:-0}))
+}
+
+for features in "" strip-descriptions strip-names; do
+    cargo build --release --features "$features" "$@"
+    strings=$(symbol_size STRINGS)
+    ends=$(symbol_size ENDS)
+    shared=$(symbol_size SHARED)
+    table=$((strings + ends + shared))
+    case $features in
+    "") budget=2048 ;;
+    strip-descriptions) budget=768 ;;
+    # Just ENDS and SHARED.
+    strip-names) budget=$((ends + shared)) ;;
+    esac
+    echo "${features:-default}: STRINGS $strings, ENDS $ends, SHARED $shared, $table of $budget bytes"
+    if [ "$ends" -eq 0 ] || [ "$table" -gt "$budget" ]; then
+        echo "${features:-default}: over budget, or no ENDS in $rlib" >&2
+        exit 1
+    fi
+done
@@ -0,0 +1,25 @@
+#!/bin/sh
+#
+# cargo test once, then the strategy module's shared suite against crate::Code, crate::Error
+# and crate::Result with each of the strategy-* features in turn, those only exist with
+# one of them picked. Then checks that picking two is the compile_error! in strategy.rs.
+#
+#   scripts/test-strategies.sh [cargo test args]
+#
+set -e
+cd "$(dirname "$0")/.."
+
+cargo test "$@"
+
+for strategy in union intersection disjoint; do
+    echo "strategy-$strategy"
+    cargo test --features "strategy-$strategy" "$@" -- strategy::test::test_selected
+done
+
+if cargo check --features strategy-union,strategy-disjoint 2>&1 |
+    grep -q "Only one of the strategy-union, strategy-intersection and strategy-disjoint"; then
+    echo "strategy-union,strategy-disjoint: compile_error! as expected"
+else
+    echo "strategy-union,strategy-disjoint: expected the compile_error!" >&2
+    exit 1
+fi
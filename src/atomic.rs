@@ -0,0 +1,213 @@
+use crate::intersection::{Error, JustError};
+use core::result::Result as CoreResult;
+use core::sync::atomic::Ordering;
+
+#[cfg(not(loom))]
+use core::sync::atomic::AtomicI32;
+#[cfg(loom)]
+use loom::sync::atomic::AtomicI32;
+
+//
+// The first error out of a number of tasks, which may be on different cores,
+//
+// static BOOT_ERROR: AtomicError = AtomicError::new();
+//
+// // On each core.
+// BOOT_ERROR.set_if_ok(start_core(id));
+//
+// // Once they're all done.
+// BOOT_ERROR.take()?;
+//
+// Once it holds an error, later ones are dropped, and set_if_ok returns the one which won.
+// It is a compare_exchange on an AtomicI32, so no locks, and no_std, but it needs 32 bit atomics.
+//
+// record() takes the same errors `?` on an Error does, anything Into<JustError>, and passes
+// the Result through, so the task can carry on with its own `?`.
+//
+// The tests run under loom with
+// RUSTFLAGS="--cfg loom" cargo test --release atomic
+//
+
+#[derive(Debug)]
+pub struct AtomicError(AtomicI32);
+
+impl AtomicError {
+    #[cfg(not(loom))]
+    pub const fn new() -> Self {
+        AtomicError(AtomicI32::new(Error::NoError as i32))
+    }
+
+    // loom's atomics can't be made in a const fn.
+    #[cfg(loom)]
+    pub fn new() -> Self {
+        AtomicError(AtomicI32::new(Error::NoError as i32))
+    }
+
+    // error_code is an Error code, only those are ever stored.
+    fn from_code(error_code: i32) -> Error {
+        // Safety: Every caller passes an Error code.
+        unsafe { core::mem::transmute::<i32, Error>(error_code) }
+    }
+
+    pub fn load(&self) -> Error {
+        AtomicError::from_code(self.0.load(Ordering::Acquire))
+    }
+
+    // Stores error if there isn't one already, returns whichever is there afterwards.
+    pub fn set_if_ok(&self, error: Error) -> Error {
+        if error == Error::NoError {
+            return self.load();
+        }
+        match self.0.compare_exchange(
+            Error::NoError as i32,
+            error as i32,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => error,
+            Err(first) => AtomicError::from_code(first),
+        }
+    }
+
+    // The error, leaving NoError behind.
+    pub fn take(&self) -> Error {
+        AtomicError::from_code(self.0.swap(Error::NoError as i32, Ordering::AcqRel))
+    }
+
+    // Not through Error's FromResidual, this isn't a `?`, so it leaves no trace frame.
+    pub fn record<T, E: Into<JustError>>(
+        &self,
+        result: CoreResult<T, E>,
+    ) -> CoreResult<T, JustError> {
+        result.map_err(|error| {
+            let just_error = error.into();
+            // JustError's codes are all Error codes.
+            self.set_if_ok(AtomicError::from_code(just_error as i32));
+            just_error
+        })
+    }
+}
+
+impl Default for AtomicError {
+    fn default() -> Self {
+        AtomicError::new()
+    }
+}
+
+// A boot task, which fails on cores past the ones there are.
+fn _start_core_(cell: &AtomicError, id: usize) -> CoreResult<usize, JustError> {
+    let id = cell.record(if id < 4 {
+        Ok(id)
+    } else {
+        Err(JustError::RangeError)
+    })?;
+    Ok(id + 1)
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_first_error_wins() {
+        let cell = AtomicError::new();
+        assert_eq!(cell.load(), Error::NoError);
+        assert_eq!(cell.set_if_ok(Error::NoError), Error::NoError);
+        assert_eq!(cell.set_if_ok(Error::DeleteFirst), Error::DeleteFirst);
+        assert_eq!(cell.set_if_ok(Error::RangeError), Error::DeleteFirst);
+        assert_eq!(cell.set_if_ok(Error::NoError), Error::DeleteFirst);
+        assert_eq!(cell.take(), Error::DeleteFirst);
+        assert_eq!(cell.load(), Error::NoError);
+        assert_eq!(cell.set_if_ok(Error::RangeError), Error::RangeError);
+    }
+
+    #[test]
+    fn test_record() {
+        let cell = AtomicError::default();
+        assert_eq!(_start_core_(&cell, 1), Ok(2));
+        assert_eq!(cell.load(), Error::NoError);
+        crate::trace::clear();
+        assert_eq!(
+            cell.record(Err::<(), _>(JustError::DeleteFirst)),
+            Err(JustError::DeleteFirst)
+        );
+        assert!(
+            crate::trace::last_error().is_empty(),
+            "record() isn't a `?`"
+        );
+        assert_eq!(_start_core_(&cell, 4), Err(JustError::RangeError));
+        assert_eq!(cell.load(), Error::DeleteFirst);
+    }
+
+    static BOOT_ERROR: AtomicError = AtomicError::new();
+
+    #[test]
+    fn test_threads() {
+        for _ in 0..100 {
+            let errors = [
+                Error::InvalidArgument,
+                Error::RangeError,
+                Error::DeleteFirst,
+                Error::NotEnoughMemory,
+            ];
+            let cell = Arc::new(AtomicError::new());
+            // All of them are spawned before any is joined.
+            let threads = errors.map(|error| {
+                let cell = cell.clone();
+                thread::spawn(move || cell.set_if_ok(error))
+            });
+            let winners = threads.map(|t| t.join().unwrap());
+            let first = cell.load();
+            assert!(errors.contains(&first));
+            assert!(winners.iter().all(|winner| *winner == first));
+        }
+    }
+
+    #[test]
+    fn test_static() {
+        assert_eq!(
+            BOOT_ERROR.set_if_ok(Error::TruncatedMessage),
+            Error::TruncatedMessage
+        );
+        assert_eq!(BOOT_ERROR.take(), Error::TruncatedMessage);
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_test {
+    use super::*;
+    use loom::sync::Arc;
+    use loom::thread;
+
+    #[test]
+    fn test_first_error_wins() {
+        loom::model(|| {
+            let cell = Arc::new(AtomicError::new());
+            let other = cell.clone();
+            let t = thread::spawn(move || other.set_if_ok(Error::RangeError));
+            let here = cell.set_if_ok(Error::DeleteFirst);
+            let there = t.join().unwrap();
+            assert_eq!(here, there);
+            assert_eq!(cell.load(), here);
+        });
+    }
+
+    #[test]
+    fn test_take() {
+        loom::model(|| {
+            let cell = Arc::new(AtomicError::new());
+            let other = cell.clone();
+            let t = thread::spawn(move || other.set_if_ok(Error::RangeError));
+            let taken = cell.take();
+            t.join().unwrap();
+            // Taken before or after the store, never both or neither.
+            match taken {
+                Error::NoError => assert_eq!(cell.load(), Error::RangeError),
+                Error::RangeError => assert_eq!(cell.load(), Error::NoError),
+                _ => panic!("{:?}", taken),
+            }
+        });
+    }
+}
@@ -0,0 +1,480 @@
+use crate::intersection;
+use core::fmt::{self, Write};
+use std::string::{String, ToString};
+use std::vec::Vec;
+use strum::EnumCount;
+
+//
+// Turning bindgen's constants into the intersection style Error/JustError pair, from a build.rs.
+//
+// With a constified enum, bindgen gives
+//
+// pub const seL4_Error_seL4_NoError: seL4_Error = 0;
+// pub const seL4_Error_seL4_InvalidArgument: seL4_Error = 1;
+// ...
+//
+// or with a rustified enum
+//
+// #[repr(u32)]
+// pub enum seL4_Error {
+//     seL4_NoError = 0,
+//     seL4_InvalidArgument = 1,
+//     ...
+// }
+//
+// and in build.rs
+//
+// let constants = Constants::from_bindgen("seL4_Error", &bindings.to_string())?;
+// let source = Generator::new(&constants).generate().expect("seL4_Error");
+// std::fs::write(out_dir.join("error.rs"), source)?;
+//
+// with include!(concat!(env!("OUT_DIR"), "/error.rs")) where the enums should go.
+// Variant names are the constant names less the type's prefix and Generator::strip_prefix,
+// seL4_Error_seL4_InvalidArgument is InvalidArgument.
+//
+// The codes have to agree, a variant with the same name as one of intersection::Error's
+// has to have the same code, which generate() checks, and the generated source checks again
+// at compile time, should the C headers and this crate drift apart after it was generated.
+// impl_intersection!'s TryFrom needs the codes to be contiguous from NoError = 0,
+// and 0 has to be NoError, that is the variant impl_intersection! treats as success.
+//
+// codegen/sel4_error.rs is what generate() gives for seL4's errors, test_fixture checks
+// that it still is, and includes it so that it has to compile.
+//
+// Any of these is an Err, which build.rs can expect() into a build error.
+//
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CodegenError {
+    // A line which starts as one of the constants, but isn't one.
+    Parse(String),
+    Duplicate(String),
+    NoNoError,
+    // Code 0 under another name.
+    NotNoError(String),
+    NotContiguous(i64),
+    Mismatch {
+        name: String,
+        expected: i32,
+        found: i64,
+    },
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CodegenError::Parse(line) => write!(f, "can't parse the bindgen constant `{}`", line),
+            CodegenError::Duplicate(name) => write!(f, "{} is there twice", name),
+            CodegenError::NoNoError => write!(f, "there is no 0 for NoError"),
+            CodegenError::NotNoError(name) => write!(f, "0 is {}, it has to be NoError", name),
+            CodegenError::NotContiguous(code) => {
+                write!(f, "the codes are not contiguous, {} is missing", code)
+            }
+            CodegenError::Mismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} is {} in the bindings, but {} in intersection::Error",
+                name, found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CodegenError {}
+
+pub struct Constants {
+    type_name: String,
+    constants: Vec<(String, i64)>,
+}
+
+impl Constants {
+    pub fn new(type_name: &str) -> Self {
+        Constants {
+            type_name: type_name.to_string(),
+            constants: Vec::new(),
+        }
+    }
+
+    // name as bindgen has it, seL4_Error_seL4_InvalidArgument, or seL4_InvalidArgument
+    // in a rustified enum.
+    pub fn constant(mut self, name: &str, code: i64) -> Self {
+        self.constants.push((name.to_string(), code));
+        self
+    }
+
+    // The `pub const <type_name>_...: <type_name> = <code>;` lines of bindgen's output,
+    // or the `<name> = <code>,` lines of `pub enum <type_name> {`, everything else is skipped.
+    pub fn from_bindgen(type_name: &str, bindings: &str) -> Result<Self, CodegenError> {
+        let mut constants = Constants::new(type_name);
+        let start = std::format!("pub const {}_", type_name);
+        let enum_start = std::format!("pub enum {} {{", type_name);
+        let mut in_enum = false;
+        for line in bindings.lines().map(str::trim) {
+            if in_enum {
+                if line == "}" {
+                    in_enum = false;
+                    continue;
+                }
+                if line.is_empty() || line.starts_with("#[") || line.starts_with("//") {
+                    continue;
+                }
+                let parse = || -> Option<(&str, i64)> {
+                    let (name, code) = line.strip_suffix(',').unwrap_or(line).split_once('=')?;
+                    Some((name.trim(), code.trim().parse().ok()?))
+                };
+                let (name, code) = parse().ok_or_else(|| CodegenError::Parse(line.to_string()))?;
+                constants = constants.constant(name, code);
+                continue;
+            }
+            if line == enum_start {
+                in_enum = true;
+                continue;
+            }
+            if !line.starts_with(&start) {
+                continue;
+            }
+            let parse = || -> Option<(&str, i64)> {
+                let (name, rest) = line.strip_prefix("pub const ")?.split_once(':')?;
+                let (ty, code) = rest.split_once('=')?;
+                if ty.trim() != type_name {
+                    return None;
+                }
+                Some((
+                    name.trim(),
+                    code.trim().strip_suffix(';')?.trim().parse().ok()?,
+                ))
+            };
+            let (name, code) = parse().ok_or_else(|| CodegenError::Parse(line.to_string()))?;
+            constants = constants.constant(name, code);
+        }
+        Ok(constants)
+    }
+}
+
+pub struct Generator<'a> {
+    constants: &'a Constants,
+    error: String,
+    just_error: String,
+    repr: String,
+    strip_prefix: String,
+    skip: Vec<String>,
+    crate_path: String,
+}
+
+// The seL4 error called name, by its Debug name, Named's are gone with strip-names.
+fn sel4_error(name: &str) -> Option<intersection::Error> {
+    (0..intersection::Error::COUNT as i32)
+        .filter_map(|error_code| intersection::Error::try_from(error_code).ok())
+        .find(|error| std::format!("{:?}", error) == name)
+}
+
+impl<'a> Generator<'a> {
+    // Error and JustError as i32, seL4_ prefixes stripped, seL4_NumErrors skipped.
+    pub fn new(constants: &'a Constants) -> Self {
+        Generator {
+            constants,
+            error: "Error".to_string(),
+            just_error: "JustError".to_string(),
+            repr: "i32".to_string(),
+            strip_prefix: "seL4_".to_string(),
+            skip: std::vec!["NumErrors".to_string()],
+            crate_path: "::error_compat_rust_experiments".to_string(),
+        }
+    }
+
+    pub fn names(mut self, error: &str, just_error: &str) -> Self {
+        self.error = error.to_string();
+        self.just_error = just_error.to_string();
+        self
+    }
+
+    pub fn repr(mut self, repr: &str) -> Self {
+        self.repr = repr.to_string();
+        self
+    }
+
+    pub fn strip_prefix(mut self, strip_prefix: &str) -> Self {
+        self.strip_prefix = strip_prefix.to_string();
+        self
+    }
+
+    // By variant name, after stripping.
+    pub fn skip(mut self, variant: &str) -> Self {
+        self.skip.push(variant.to_string());
+        self
+    }
+
+    // Where this crate is, for the generated impl_intersection!.
+    pub fn crate_path(mut self, crate_path: &str) -> Self {
+        self.crate_path = crate_path.to_string();
+        self
+    }
+
+    // The variants in code order, with their codes checked.
+    fn variants(&self) -> Result<Vec<(String, i64)>, CodegenError> {
+        let type_prefix = std::format!("{}_", self.constants.type_name);
+        let mut variants: Vec<(String, i64)> = Vec::new();
+        for (name, code) in &self.constants.constants {
+            let name = name.strip_prefix(&type_prefix).unwrap_or(name);
+            let name = name.strip_prefix(&self.strip_prefix).unwrap_or(name);
+            if self.skip.iter().any(|skip| skip == name) {
+                continue;
+            }
+            if variants
+                .iter()
+                .any(|(other, other_code)| other == name || other_code == code)
+            {
+                return Err(CodegenError::Duplicate(name.to_string()));
+            }
+            if let Some(error) = sel4_error(name) {
+                if error as i64 != *code {
+                    return Err(CodegenError::Mismatch {
+                        name: name.to_string(),
+                        expected: error as i32,
+                        found: *code,
+                    });
+                }
+            }
+            variants.push((name.to_string(), *code));
+        }
+        variants.sort_by_key(|(_, code)| *code);
+        match variants.first() {
+            Some((name, 0)) if name == "NoError" => (),
+            Some((name, 0)) => return Err(CodegenError::NotNoError(name.clone())),
+            _ => return Err(CodegenError::NoNoError),
+        }
+        for (expected, (_, code)) in variants.iter().enumerate() {
+            if *code != expected as i64 {
+                return Err(CodegenError::NotContiguous(expected as i64));
+            }
+        }
+        Ok(variants)
+    }
+
+    pub fn generate(&self) -> Result<String, CodegenError> {
+        let variants = self.variants()?;
+        let (error, just_error, repr, krate) =
+            (&self.error, &self.just_error, &self.repr, &self.crate_path);
+        let mut source = String::new();
+        // Writing to a String can't fail.
+        let mut line = |line: fmt::Arguments| {
+            source.write_fmt(line).unwrap();
+            source.push('\n');
+        };
+        line(format_args!(
+            "// Generated from {}, do not edit.",
+            self.constants.type_name
+        ));
+        line(format_args!("#[derive(Copy, Clone, Debug, Eq, PartialEq)]"));
+        line(format_args!("#[repr({})]", repr));
+        line(format_args!("#[allow(clippy::enum_variant_names)]"));
+        line(format_args!("pub enum {} {{", error));
+        for (name, code) in &variants {
+            line(format_args!("    {} = {},", name, code));
+        }
+        line(format_args!("}}"));
+        line(format_args!(""));
+        line(format_args!("#[derive(Copy, Clone, Debug, Eq, PartialEq)]"));
+        line(format_args!("#[repr({})]", repr));
+        line(format_args!("#[allow(clippy::enum_variant_names)]"));
+        line(format_args!("pub enum {} {{", just_error));
+        for (name, code) in &variants[1..] {
+            line(format_args!("    {} = {},", name, code));
+        }
+        line(format_args!("}}"));
+        line(format_args!(""));
+        line(format_args!(
+            "impl {}::repr::EnumCount for {} {{",
+            krate, error
+        ));
+        line(format_args!("    const COUNT: usize = {};", variants.len()));
+        line(format_args!("}}"));
+        line(format_args!(""));
+        line(format_args!(
+            "{}::impl_intersection!({}, {}, {});",
+            krate, error, just_error, repr
+        ));
+        for (name, _) in &variants {
+            if sel4_error(name).is_some() {
+                line(format_args!(""));
+                line(format_args!(
+                    "const _: () = if {}::{} as i64 == {}::intersection::Error::{} as i64 {{",
+                    error, name, krate, name
+                ));
+                line(format_args!("}} else {{"));
+                line(format_args!("    panic!()"));
+                line(format_args!("}};"));
+            }
+        }
+        Ok(source)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::result::Result as CoreResult;
+
+    const BINDINGS: &str = "
+pub type seL4_Error = ::std::os::raw::c_uint;
+pub const seL4_Error_seL4_NoError: seL4_Error = 0;
+pub const seL4_Error_seL4_InvalidArgument: seL4_Error = 1;
+pub const seL4_Error_seL4_InvalidCapability: seL4_Error = 2;
+pub const seL4_Error_seL4_IllegalOperation: seL4_Error = 3;
+pub const seL4_Error_seL4_NumErrors: seL4_Error = 4;
+pub const seL4_WordBits: u32 = 64;
+";
+
+    const RUSTIFIED: &str = "
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum seL4_Error {
+    seL4_NoError = 0,
+    seL4_InvalidArgument = 1,
+    seL4_InvalidCapability = 2,
+    seL4_IllegalOperation = 3,
+    seL4_RangeError = 4,
+    seL4_AlignmentError = 5,
+    seL4_FailedLookup = 6,
+    seL4_TruncatedMessage = 7,
+    seL4_DeleteFirst = 8,
+    seL4_RevokeFirst = 9,
+    seL4_NotEnoughMemory = 10,
+    seL4_NumErrors = 11,
+}
+pub const seL4_WordBits: u32 = 64;
+";
+
+    // What RUSTIFIED generates, checked in, so this is compiled and its `?` tested.
+    mod generated {
+        include!("codegen/sel4_error.rs");
+    }
+
+    #[test]
+    fn test_from_bindgen() {
+        let constants = Constants::from_bindgen("seL4_Error", BINDINGS).unwrap();
+        assert_eq!(constants.constants.len(), 5);
+        assert_eq!(
+            constants.constants[1],
+            ("seL4_Error_seL4_InvalidArgument".to_string(), 1)
+        );
+        assert_eq!(
+            Constants::from_bindgen(
+                "seL4_Error",
+                "pub const seL4_Error_seL4_NoError: seL4_Error = x;"
+            )
+            .err(),
+            Some(CodegenError::Parse(
+                "pub const seL4_Error_seL4_NoError: seL4_Error = x;".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_from_rustified() {
+        let constants = Constants::from_bindgen("seL4_Error", RUSTIFIED).unwrap();
+        assert_eq!(constants.constants.len(), 12);
+        assert_eq!(
+            constants.constants[1],
+            ("seL4_InvalidArgument".to_string(), 1)
+        );
+        assert_eq!(
+            Constants::from_bindgen("seL4_Error", "pub enum seL4_Error {\n    seL4_NoError,\n}")
+                .err(),
+            Some(CodegenError::Parse("seL4_NoError,".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_fixture() {
+        let constants = Constants::from_bindgen("seL4_Error", RUSTIFIED).unwrap();
+        let source = Generator::new(&constants)
+            .repr("u32")
+            .crate_path("crate")
+            .generate()
+            .unwrap();
+        assert_eq!(source, include_str!("codegen/sel4_error.rs"));
+    }
+
+    fn _test_generated_(error: generated::Error) -> generated::Error {
+        error?;
+        generated::Error::NoError
+    }
+
+    #[test]
+    fn test_generated() {
+        use generated::{Error, JustError};
+        assert_eq!(Error::try_from(4), Ok(Error::RangeError));
+        assert!(Error::try_from(11).is_err());
+        assert_eq!(_test_generated_(Error::NoError), Error::NoError);
+        assert_eq!(_test_generated_(Error::DeleteFirst), Error::DeleteFirst);
+        assert_eq!(
+            CoreResult::<(), JustError>::from(Error::FailedLookup),
+            Err(JustError::FailedLookup)
+        );
+    }
+
+    #[test]
+    fn test_generate() {
+        let constants = Constants::from_bindgen("seL4_Error", BINDINGS).unwrap();
+        let source = Generator::new(&constants).repr("u32").generate().unwrap();
+        assert!(source.contains("pub enum Error {\n    NoError = 0,\n    InvalidArgument = 1,"));
+        assert!(source.contains("pub enum JustError {\n    InvalidArgument = 1,"));
+        assert!(source.contains("const COUNT: usize = 4;"));
+        assert!(source.contains(
+            "::error_compat_rust_experiments::impl_intersection!(Error, JustError, u32);"
+        ));
+        assert!(!source.contains("NumErrors"));
+    }
+
+    #[test]
+    fn test_mismatch() {
+        let constants = Constants::new("seL4_Error")
+            .constant("seL4_Error_seL4_NoError", 0)
+            .constant("seL4_Error_seL4_RangeError", 1);
+        assert_eq!(
+            Generator::new(&constants).generate(),
+            Err(CodegenError::Mismatch {
+                name: "RangeError".to_string(),
+                expected: 4,
+                found: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_codes() {
+        let constants = Constants::new("status")
+            .constant("status_OK", 1)
+            .constant("status_BUSY", 2);
+        assert_eq!(
+            Generator::new(&constants).generate(),
+            Err(CodegenError::NoNoError)
+        );
+        let constants = Constants::new("status")
+            .constant("status_OK", 0)
+            .constant("status_BUSY", 1);
+        assert_eq!(
+            Generator::new(&constants).generate(),
+            Err(CodegenError::NotNoError("OK".to_string()))
+        );
+        let constants = Constants::new("status")
+            .constant("status_NoError", 0)
+            .constant("status_BUSY", 2);
+        assert_eq!(
+            Generator::new(&constants).generate(),
+            Err(CodegenError::NotContiguous(1))
+        );
+        let constants = Constants::new("status")
+            .constant("status_NoError", 0)
+            .constant("status_BUSY", 1)
+            .constant("status_AGAIN", 1);
+        assert_eq!(
+            Generator::new(&constants).generate(),
+            Err(CodegenError::Duplicate("AGAIN".to_string()))
+        );
+    }
+}
@@ -0,0 +1,94 @@
+// Generated from seL4_Error, do not edit.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+#[allow(clippy::enum_variant_names)]
+pub enum Error {
+    NoError = 0,
+    InvalidArgument = 1,
+    InvalidCapability = 2,
+    IllegalOperation = 3,
+    RangeError = 4,
+    AlignmentError = 5,
+    FailedLookup = 6,
+    TruncatedMessage = 7,
+    DeleteFirst = 8,
+    RevokeFirst = 9,
+    NotEnoughMemory = 10,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+#[allow(clippy::enum_variant_names)]
+pub enum JustError {
+    InvalidArgument = 1,
+    InvalidCapability = 2,
+    IllegalOperation = 3,
+    RangeError = 4,
+    AlignmentError = 5,
+    FailedLookup = 6,
+    TruncatedMessage = 7,
+    DeleteFirst = 8,
+    RevokeFirst = 9,
+    NotEnoughMemory = 10,
+}
+
+impl crate::repr::EnumCount for Error {
+    const COUNT: usize = 11;
+}
+
+crate::impl_intersection!(Error, JustError, u32);
+
+const _: () = if Error::NoError as i64 == crate::intersection::Error::NoError as i64 {
+} else {
+    panic!()
+};
+
+const _: () = if Error::InvalidArgument as i64 == crate::intersection::Error::InvalidArgument as i64 {
+} else {
+    panic!()
+};
+
+const _: () = if Error::InvalidCapability as i64 == crate::intersection::Error::InvalidCapability as i64 {
+} else {
+    panic!()
+};
+
+const _: () = if Error::IllegalOperation as i64 == crate::intersection::Error::IllegalOperation as i64 {
+} else {
+    panic!()
+};
+
+const _: () = if Error::RangeError as i64 == crate::intersection::Error::RangeError as i64 {
+} else {
+    panic!()
+};
+
+const _: () = if Error::AlignmentError as i64 == crate::intersection::Error::AlignmentError as i64 {
+} else {
+    panic!()
+};
+
+const _: () = if Error::FailedLookup as i64 == crate::intersection::Error::FailedLookup as i64 {
+} else {
+    panic!()
+};
+
+const _: () = if Error::TruncatedMessage as i64 == crate::intersection::Error::TruncatedMessage as i64 {
+} else {
+    panic!()
+};
+
+const _: () = if Error::DeleteFirst as i64 == crate::intersection::Error::DeleteFirst as i64 {
+} else {
+    panic!()
+};
+
+const _: () = if Error::RevokeFirst as i64 == crate::intersection::Error::RevokeFirst as i64 {
+} else {
+    panic!()
+};
+
+const _: () = if Error::NotEnoughMemory as i64 == crate::intersection::Error::NotEnoughMemory as i64 {
+} else {
+    panic!()
+};
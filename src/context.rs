@@ -0,0 +1,212 @@
+use crate::intersection::{Error, JustError};
+use core::fmt;
+use core::result::Result as CoreResult;
+
+//
+// anyhow style context, without the allocation.
+//
+// Contextual<E> is the error plus a fixed number of &'static str frames stored inline,
+// once it is full any further context is dropped (the innermost context is kept, as it is
+// usually the most specific). It is Copy, so it can go anywhere a JustError can.
+//
+// At the FFI boundary `?` turns it back into the plain C error code,
+// so the context only lives on the rust side.
+//
+
+pub const MAX_FRAMES: usize = 4;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Contextual<E> {
+    error: E,
+    frames: [&'static str; MAX_FRAMES],
+    len: u8,
+    dropped: u8,
+}
+
+impl<E> Contextual<E> {
+    pub const fn new(error: E) -> Self {
+        Contextual {
+            error,
+            frames: [""; MAX_FRAMES],
+            len: 0,
+            dropped: 0,
+        }
+    }
+
+    pub fn push(mut self, context: &'static str) -> Self {
+        match self.frames.get_mut(self.len as usize) {
+            Some(frame) => {
+                *frame = context;
+                self.len += 1;
+            }
+            None => self.dropped = self.dropped.saturating_add(1),
+        }
+        self
+    }
+
+    pub fn error(&self) -> &E {
+        &self.error
+    }
+
+    pub fn into_inner(self) -> E {
+        self.error
+    }
+
+    // Innermost first.
+    pub fn frames(&self) -> &[&'static str] {
+        &self.frames[..self.len as usize]
+    }
+
+    // How many frames didn't fit.
+    pub fn dropped(&self) -> usize {
+        self.dropped as usize
+    }
+}
+
+impl<E> From<E> for Contextual<E> {
+    fn from(error: E) -> Self {
+        Contextual::new(error)
+    }
+}
+
+// The FFI boundary, the context is dropped leaving just the error code. This is also what
+// impl_intersection!'s FromResidual goes through for `?` on a Contextual in a function returning Error.
+impl From<Contextual<JustError>> for JustError {
+    fn from(contextual: Contextual<JustError>) -> Self {
+        contextual.error
+    }
+}
+
+// Outermost first, e.g. "while booting: while retyping untyped: NotEnoughMemory"
+impl<E: fmt::Debug> fmt::Display for Contextual<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.dropped != 0 {
+            write!(f, "...: ")?;
+        }
+        for frame in self.frames().iter().rev() {
+            write!(f, "{}: ", frame)?;
+        }
+        write!(f, "{:?}", self.error)
+    }
+}
+
+pub trait Context<T, E> {
+    fn context(self, context: &'static str) -> CoreResult<T, Contextual<E>>;
+
+    fn with_context<F>(self, f: F) -> CoreResult<T, Contextual<E>>
+    where
+        F: FnOnce() -> &'static str;
+}
+
+impl<T> Context<T, JustError> for CoreResult<T, JustError> {
+    fn context(self, context: &'static str) -> CoreResult<T, Contextual<JustError>> {
+        self.map_err(|error| Contextual::new(error).push(context))
+    }
+
+    fn with_context<F>(self, f: F) -> CoreResult<T, Contextual<JustError>>
+    where
+        F: FnOnce() -> &'static str,
+    {
+        self.map_err(|error| Contextual::new(error).push(f()))
+    }
+}
+
+impl<T, E> Context<T, E> for CoreResult<T, Contextual<E>> {
+    fn context(self, context: &'static str) -> CoreResult<T, Contextual<E>> {
+        self.map_err(|contextual| contextual.push(context))
+    }
+
+    fn with_context<F>(self, f: F) -> CoreResult<T, Contextual<E>>
+    where
+        F: FnOnce() -> &'static str,
+    {
+        self.map_err(|contextual| contextual.push(f()))
+    }
+}
+
+// So you can go straight from the C error code, Error::RangeError.context("...")
+impl Context<(), JustError> for Error {
+    fn context(self, context: &'static str) -> CoreResult<(), Contextual<JustError>> {
+        CoreResult::<(), JustError>::from(self).context(context)
+    }
+
+    fn with_context<F>(self, f: F) -> CoreResult<(), Contextual<JustError>>
+    where
+        F: FnOnce() -> &'static str,
+    {
+        CoreResult::<(), JustError>::from(self).with_context(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intersection::Error;
+
+    fn _retype_(error: Error) -> CoreResult<(), Contextual<JustError>> {
+        error.context("while retyping untyped")
+    }
+
+    fn _boot_(error: Error) -> CoreResult<(), Contextual<JustError>> {
+        _retype_(error).with_context(|| "while booting")?;
+        Ok(())
+    }
+
+    fn _no_context_() -> CoreResult<(), Contextual<JustError>> {
+        Ok(Error::RangeError?)
+    }
+
+    extern "C" fn _ffi_boot_(error: Error) -> Error {
+        _boot_(error)?;
+        Error::NoError
+    }
+
+    fn _deep_(n: usize) -> CoreResult<(), Contextual<JustError>> {
+        if n == 0 {
+            Error::DeleteFirst.context("0")
+        } else {
+            _deep_(n - 1).context("n")
+        }
+    }
+
+    #[test]
+    fn test_ok() {
+        assert_eq!(_boot_(Error::NoError), Ok(()));
+    }
+
+    #[test]
+    fn test_context() {
+        let contextual = _boot_(Error::NotEnoughMemory).unwrap_err();
+        assert_eq!(contextual.error(), &JustError::NotEnoughMemory);
+        assert_eq!(
+            contextual.frames(),
+            &["while retyping untyped", "while booting"]
+        );
+        assert_eq!(
+            std::format!("{}", contextual),
+            "while booting: while retyping untyped: NotEnoughMemory"
+        );
+    }
+
+    #[test]
+    fn test_no_context() {
+        let contextual = _no_context_().unwrap_err();
+        assert_eq!(contextual.frames(), &[] as &[&str]);
+        assert_eq!(JustError::from(contextual), JustError::RangeError);
+    }
+
+    #[test]
+    fn test_ffi_boundary() {
+        assert_eq!(_ffi_boot_(Error::NoError), Error::NoError);
+        assert_eq!(_ffi_boot_(Error::FailedLookup), Error::FailedLookup);
+    }
+
+    #[test]
+    fn test_dropped() {
+        let contextual = _deep_(MAX_FRAMES + 1).unwrap_err();
+        assert_eq!(contextual.frames().len(), MAX_FRAMES);
+        assert_eq!(contextual.frames()[0], "0");
+        assert_eq!(contextual.dropped(), 2);
+        assert!(std::format!("{}", contextual).starts_with("...: n: "));
+    }
+}
@@ -0,0 +1,38 @@
+use crate::disjoint::{Error, NonError, Result};
+use crate::hresult::HResult;
+use crate::signed::SignedResult;
+use core::result::Result as CoreResult;
+
+//
+// defmt for the error enums is just #[derive(defmt::Format)] on each of them,
+// defmt interns the variant names into the ELF so only an index goes over the wire,
+// and none of the Debug derive ends up in the binary.
+//
+// disjoint::Result is a union so it can't be derived, it is formatted as whichever
+// of NonError or Error it holds. HResult and SignedResult are formatted the way their
+// Debug impls are, and error_domain! enums get theirs from the macro.
+//
+
+impl defmt::Format for Result {
+    fn format(&self, f: defmt::Formatter) {
+        match CoreResult::<NonError, Error>::from(*self) {
+            Ok(non_error) => defmt::write!(f, "Ok({})", non_error),
+            Err(error) => defmt::write!(f, "Err({})", error),
+        }
+    }
+}
+
+impl defmt::Format for HResult {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "HResult({=u32:#x})", self.0)
+    }
+}
+
+impl<T> defmt::Format for SignedResult<T> {
+    fn format(&self, f: defmt::Formatter) {
+        match self.errno_code() {
+            Some(error_code) => defmt::write!(f, "errno: {=i32}", error_code),
+            None => defmt::write!(f, "ok: {=usize}", self.raw() as usize),
+        }
+    }
+}
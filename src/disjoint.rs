@@ -1,19 +1,26 @@
+use crate::repr::Repr;
+use core::convert::Infallible;
 use core::fmt::Debug;
-use core::num::NonZeroI32;
 use core::ops::ControlFlow;
 use core::ops::FromResidual;
 use core::ops::Try;
 use core::result::Result as CoreResult;
 use enum_extra::NonZeroRepr;
-use enum_extra_derive::NonZeroRepr;
 use strum::EnumMetadata;
-use strum_macros::EnumMetadata;
 
 //
 // We should probably just dustbin this implementation,
 // I only leave it in for completeness.
 // See lib.rs for more comments.
 //
+// The one thing it does have going for it is room for more than one non error code.
+// The errors keep their seL4 values, so the extra NonError codes count down from 0,
+// NonError::FLOOR..=0 are non errors and the positive codes are errors.
+//
+// Some of the non errors are warnings, `?` still continues with them, and the warning is
+// the NonError it evaluates to, so a caller which cares checks is_warning() on that.
+// branch() doesn't record it anywhere else.
+//
 
 impl From<CoreResult<NonError, Error>> for Result {
     fn from(result: CoreResult<NonError, Error>) -> Result {
@@ -26,11 +33,15 @@ impl From<CoreResult<NonError, Error>> for Result {
 
 impl From<Result> for CoreResult<NonError, Error> {
     fn from(it: Result) -> Self {
-        match NonZeroI32::new(unsafe { it.error_code }) {
+        let error_code = unsafe { it.error_code };
+        match error_code.non_zero() {
             None => unsafe { CoreResult::Ok(it.non_error) },
-            Some(e) if (/* TODO derive Error::FLOOR */ Error::InvalidArgument as i32 .. Error::COUNT as i32).contains(&e.into()) => {
+            Some(e) if (/* TODO derive Error::FLOOR */ Error::InvalidArgument as i32 .. Error::COUNT as i32).contains(&e.get()) => {
                 CoreResult::Err(unsafe { it.error })
             }
+            Some(_) if (NonError::FLOOR as i32..0).contains(&error_code) => {
+                CoreResult::Ok(unsafe { it.non_error })
+            }
             _ => {
                 CoreResult::Err(Error::ErrorCodeOutOfRange)
             }
@@ -43,13 +54,10 @@ impl Try for Result {
     type Residual = Error;
 
     fn branch(self) -> ControlFlow<Self::Residual, NonError> {
-        match NonZeroI32::new(unsafe { self.error_code }) {
-				None => { ControlFlow::Continue(unsafe { self.non_error}) }
-				Some(e) if (/* TODO derive Error::FLOOR */ Error::InvalidArgument as i32 .. Error::COUNT as i32).contains(&e.into()) => {
-					ControlFlow::Break(unsafe { self.error } )
-				}
-				_ => { ControlFlow::Break(Error::ErrorCodeOutOfRange) }
-        	}
+        match CoreResult::<NonError, Error>::from(self) {
+            CoreResult::Ok(non_error) => ControlFlow::Continue(non_error),
+            CoreResult::Err(error) => ControlFlow::Break(error),
+        }
     }
 
     fn from_output(ok: NonError) -> Self {
@@ -63,9 +71,31 @@ impl FromResidual<Error> for Result {
     }
 }
 
+// Anything which converts into an Error, so `?` works on idiomatic Rust helpers too.
+impl<E: Into<Error>> FromResidual<CoreResult<Infallible, E>> for Result {
+    fn from_residual(result: CoreResult<Infallible, E>) -> Self {
+        match result {
+            // This should never happen because this is along the Continue branch.
+            CoreResult::Ok(_) => Result::OK,
+            CoreResult::Err(error) => Result {
+                error: error.into(),
+            },
+        }
+    }
+}
+
+// `?` on a None is FailedLookup, as it is for union and intersection.
+impl FromResidual<Option<Infallible>> for Result {
+    fn from_residual(_: Option<Infallible>) -> Self {
+        Result {
+            error: Error::FailedLookup,
+        }
+    }
+}
+
 #[derive(Copy, Clone)]
 #[repr(C)]
-union Result {
+pub union Result {
     non_error: NonError,
     error: Error,
     error_code: i32,
@@ -77,6 +107,17 @@ impl Debug for Result {
     }
 }
 
+impl Result {
+    pub const OK: Result = Result {
+        non_error: NonError::Ok,
+    };
+
+    // The code as C sees it, which needn't be any of NonError or Error.
+    pub fn error_code(self) -> i32 {
+        unsafe { self.error_code }
+    }
+}
+
 impl PartialEq for Result {
     fn eq(&self, other: &Self) -> bool {
         let me: i32 = unsafe { self.error_code };
@@ -85,16 +126,51 @@ impl PartialEq for Result {
     }
 }
 
-#[derive(Copy, Clone, Debug, EnumMetadata)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, EnumMetadata)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(i32)]
-enum NonError {
+pub enum NonError {
     Ok = 0,
+    // Some, but not all, of the work was done.
+    OkPartial = -1,
+    WarnTruncated = -2,
+    WarnDeprecated = -3,
+}
+
+impl NonError {
+    pub const FLOOR: NonError = NonError::WarnDeprecated;
+
+    pub fn is_warning(self) -> bool {
+        matches!(self, NonError::WarnTruncated | NonError::WarnDeprecated)
+    }
+}
+
+// Compile time check that the NonError discriminants are contiguous down from 0,
+// branch() relies on it.
+const _: () = if NonError::FLOOR as i32 == 1 - NonError::COUNT as i32 {
+} else {
+    panic!()
+};
+
+// The checked way in, for codes which didn't come from a NonError to begin with.
+impl TryFrom<i32> for NonError {
+    type Error = i32;
+
+    fn try_from(error_code: i32) -> CoreResult<Self, i32> {
+        if (NonError::FLOOR as i32..=NonError::Ok as i32).contains(&error_code) {
+            // Safety: The discriminants of NonError are contiguous from FLOOR to Ok.
+            Ok(unsafe { core::mem::transmute(error_code) })
+        } else {
+            Err(error_code)
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, NonZeroRepr, EnumMetadata)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(i32)]
 #[allow(clippy::enum_variant_names)]
-enum Error {
+pub enum Error {
     InvalidArgument = (NonError::Ok as i32) + 1_i32,
     InvalidCapability,
     IllegalOperation,
@@ -110,6 +186,21 @@ enum Error {
     /* NumErrors has been moved out of the enum into a Error::COUNT */
 }
 
+// The checked way in, for codes which didn't come from an Error to begin with.
+impl TryFrom<i32> for Error {
+    type Error = i32;
+
+    fn try_from(error_code: i32) -> CoreResult<Self, i32> {
+        if (Error::InvalidArgument as i32..=Error::ErrorCodeOutOfRange as i32).contains(&error_code)
+        {
+            // Safety: The discriminants of Error are contiguous from InvalidArgument to ErrorCodeOutOfRange.
+            Ok(unsafe { core::mem::transmute(error_code) })
+        } else {
+            Err(error_code)
+        }
+    }
+}
+
 fn _test_ok_1_() -> Result {
     // OK so it is a little more verbose than Ok(Result::Ok?)
     Result {
@@ -221,6 +312,63 @@ fn _test_error_code_out_of_range_really_() -> Result {
     }
 }
 
+fn _test_ok_partial_() -> Result {
+    Result {
+        non_error: Result {
+            non_error: NonError::OkPartial,
+        }?,
+    }
+}
+
+fn _test_warning_() -> Result {
+    Result {
+        non_error: Result {
+            non_error: NonError::WarnTruncated,
+        }?,
+    }
+}
+
+// What a caller which cares about warnings does with them.
+fn _test_count_warnings_(results: &[Result], warnings: &mut usize) -> Result {
+    for result in results {
+        if (*result)?.is_warning() {
+            *warnings += 1;
+        }
+    }
+    Result::OK
+}
+
+fn _test_below_floor_() -> Result {
+    Result {
+        non_error: Result {
+            error_code: NonError::FLOOR as i32 - 1,
+        }?,
+    }
+}
+
+// Idiomatic Rust helpers, which know nothing of Result.
+struct Exhausted;
+
+impl From<Exhausted> for Error {
+    fn from(_: Exhausted) -> Error {
+        Error::NotEnoughMemory
+    }
+}
+
+fn _allocate_(free: usize) -> CoreResult<usize, Exhausted> {
+    free.checked_sub(1).ok_or(Exhausted)
+}
+
+fn _test_core_result_(free: usize) -> Result {
+    let _ = _allocate_(free)?;
+    Result::OK
+}
+
+fn _test_option_(slots: &[u8], wanted: u8) -> Result {
+    let _ = slots.iter().position(|slot| *slot == wanted)?;
+    Result::OK
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -352,4 +500,88 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn test_ok_partial() {
+        assert_eq!(
+            _test_ok_partial_(),
+            Result {
+                non_error: NonError::OkPartial
+            }
+        );
+    }
+
+    #[test]
+    fn test_warning() {
+        assert_eq!(
+            _test_warning_(),
+            Result {
+                non_error: NonError::WarnTruncated
+            }
+        );
+        assert!(!NonError::OkPartial.is_warning());
+        let warned = Result {
+            non_error: NonError::WarnDeprecated,
+        };
+        let partial = Result {
+            non_error: NonError::OkPartial,
+        };
+        let mut warnings = 0;
+        assert_eq!(
+            _test_count_warnings_(&[warned, Result::OK, partial, warned], &mut warnings),
+            Result::OK
+        );
+        assert_eq!(warnings, 2);
+        let failed = Result {
+            error: Error::RangeError,
+        };
+        assert_eq!(
+            _test_count_warnings_(&[warned, failed, warned], &mut warnings),
+            failed
+        );
+        assert_eq!(warnings, 3);
+    }
+
+    #[test]
+    fn test_below_floor() {
+        assert_eq!(
+            _test_below_floor_(),
+            Result {
+                error: Error::ErrorCodeOutOfRange
+            }
+        );
+    }
+
+    #[test]
+    fn test_core_result() {
+        assert_eq!(
+            CoreResult::<NonError, Error>::from(Result {
+                non_error: NonError::WarnDeprecated
+            })
+            .ok(),
+            Some(NonError::WarnDeprecated)
+        );
+    }
+
+    #[test]
+    fn test_question_mark_core_result() {
+        assert_eq!(_test_core_result_(1), Result::OK);
+        assert_eq!(
+            _test_core_result_(0),
+            Result {
+                error: Error::NotEnoughMemory
+            }
+        );
+    }
+
+    #[test]
+    fn test_question_mark_option() {
+        assert_eq!(_test_option_(&[1, 2], 2), Result::OK);
+        assert_eq!(
+            _test_option_(&[1, 2], 3),
+            Result {
+                error: Error::FailedLookup
+            }
+        );
+    }
 }
@@ -0,0 +1,289 @@
+use crate::intersection::{Error, JustError};
+use core::result::Result as CoreResult;
+
+//
+// Layered error domains, e.g. a driver framework's errors on top of seL4's,
+// and an application's on top of the driver framework's.
+//
+// error_domain! declares an intersection style Error/JustError pair which embeds
+// the codes of other domains at an offset, alongside codes of its own:
+//
+// error_domain! {
+//     pub enum DriverError, JustDriverError: i32 {
+//         [JustError at 0] { InvalidArgument, ..., NotEnoughMemory }
+//         DeviceBusy = 100,
+//         Timeout = 101,
+//     }
+// }
+//
+// The embedded codes are the inner domain's codes plus the offset. Along with what
+// impl_intersection! generates, each embedded domain gets From<Inner> for JustDriverError,
+// and TryFrom<JustDriverError> for Inner. The From is what impl_intersection!'s FromResidual
+// goes through, so that `?` on an Inner (or on intersection::Error, whose residual is a JustError)
+// returns a DriverError.
+//
+// The checks are all done by rustc, overlapping discriminants are E0081,
+// and leaving out one of the inner domain's variants makes From<Inner> a non-exhaustive match.
+//
+// `?` only goes up one layer, an application domain embedding JustDriverError can't `?`
+// an intersection::Error directly, it has to go through JustDriverError::from().
+//
+
+#[macro_export]
+macro_rules! error_domain {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $error:ident, $just_error:ident: $repr:ident {
+            $(
+                [$inner:ident at $offset:expr] { $($embedded:ident),* $(,)? }
+            )*
+            $($variant:ident = $code:expr),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+        #[repr($repr)]
+        #[allow(clippy::enum_variant_names)]
+        $vis enum $error {
+            NoError = 0,
+            $($($embedded = ($offset) + $inner::$embedded as $repr,)*)*
+            $($variant = $code,)*
+        }
+
+        $(#[$meta])*
+        #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+        #[repr($repr)]
+        #[allow(clippy::enum_variant_names)]
+        $vis enum $just_error {
+            $($($embedded = ($offset) + $inner::$embedded as $repr,)*)*
+            $($variant = $code,)*
+        }
+
+        $crate::impl_intersection!(@try $error, $just_error, $repr);
+        $crate::__domain_defmt!($error { NoError, $($($embedded,)*)* $($variant,)* });
+        $crate::__domain_defmt!($just_error { $($($embedded,)*)* $($variant,)* });
+        $crate::__domain_udebug!($error { NoError, $($($embedded,)*)* $($variant,)* });
+        $crate::__domain_udebug!($just_error { $($($embedded,)*)* $($variant,)* });
+
+        // The discriminants aren't contiguous, so this can't be the one from impl_intersection!
+        impl ::core::convert::TryFrom<$repr> for $error {
+            type Error = $repr;
+
+            fn try_from(error_code: $repr) -> ::core::result::Result<Self, $repr> {
+                if error_code == $error::NoError as $repr {
+                    return Ok($error::NoError);
+                }
+                $($(
+                    if error_code == $error::$embedded as $repr {
+                        return Ok($error::$embedded);
+                    }
+                )*)*
+                $(
+                    if error_code == $error::$variant as $repr {
+                        return Ok($error::$variant);
+                    }
+                )*
+                Err(error_code)
+            }
+        }
+
+        $(
+            impl ::core::convert::From<$inner> for $just_error {
+                fn from(inner: $inner) -> Self {
+                    match inner {
+                        $($inner::$embedded => $just_error::$embedded,)*
+                    }
+                }
+            }
+
+            impl ::core::convert::TryFrom<$just_error> for $inner {
+                type Error = $just_error;
+
+                #[allow(unreachable_patterns)]
+                fn try_from(it: $just_error) -> ::core::result::Result<Self, $just_error> {
+                    match it {
+                        $($just_error::$embedded => Ok($inner::$embedded),)*
+                        _ => Err(it),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+// defmt::Format and ufmt::uDebug for the error_domain! enums, by variant name.
+//
+// Whether they are there depends on this crate's defmt and ufmt features, not on the features of
+// the crate using error_domain!, hence a version of each for either way. They go through this
+// crate's re-exports, so that crate doesn't need defmt or ufmt as dependencies of its own.
+#[cfg(feature = "defmt")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __domain_defmt {
+    ($ty:ident { $($variant:ident,)* }) => {
+        impl $crate::__defmt::Format for $ty {
+            fn format(&self, f: $crate::__defmt::Formatter) {
+                let name = match self {
+                    $($ty::$variant => stringify!($variant),)*
+                };
+                <str as $crate::__defmt::Format>::format(name, f)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "defmt"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __domain_defmt {
+    ($($tt:tt)*) => {};
+}
+
+#[cfg(feature = "ufmt")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __domain_udebug {
+    ($ty:ident { $($variant:ident,)* }) => {
+        impl $crate::__ufmt::uDebug for $ty {
+            fn fmt<W: $crate::__ufmt::uWrite + ?Sized>(
+                &self,
+                f: &mut $crate::__ufmt::Formatter<'_, W>,
+            ) -> ::core::result::Result<(), W::Error> {
+                f.write_str(match self {
+                    $($ty::$variant => stringify!($variant),)*
+                })
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "ufmt"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __domain_udebug {
+    ($($tt:tt)*) => {};
+}
+
+// A driver framework on top of seL4.
+crate::error_domain! {
+    enum DriverError, JustDriverError: i32 {
+        [JustError at 0] {
+            InvalidArgument,
+            InvalidCapability,
+            IllegalOperation,
+            RangeError,
+            AlignmentError,
+            FailedLookup,
+            TruncatedMessage,
+            DeleteFirst,
+            RevokeFirst,
+            NotEnoughMemory,
+        }
+        DeviceBusy = 100,
+        Timeout = 101,
+    }
+}
+
+// An application on top of the driver framework.
+crate::error_domain! {
+    enum AppError, JustAppError: i32 {
+        [JustDriverError at 1000] {
+            InvalidArgument,
+            InvalidCapability,
+            IllegalOperation,
+            RangeError,
+            AlignmentError,
+            FailedLookup,
+            TruncatedMessage,
+            DeleteFirst,
+            RevokeFirst,
+            NotEnoughMemory,
+            DeviceBusy,
+            Timeout,
+        }
+        BadConfig = 1,
+    }
+}
+
+fn _test_ok_() -> DriverError {
+    Error::NoError?;
+    DriverError::NoError
+}
+
+fn _test_sel4_(error: Error) -> DriverError {
+    error?;
+    DriverError::NoError
+}
+
+fn _test_driver_(error: DriverError) -> AppError {
+    error?;
+    AppError::NoError
+}
+
+fn _test_own_() -> CoreResult<(), JustDriverError> {
+    Ok(DriverError::Timeout?)
+}
+
+// Two layers down, one JustDriverError::from() at a time.
+fn _test_two_layers_(error: Error) -> AppError {
+    CoreResult::<(), JustError>::from(error).map_err(JustDriverError::from)?;
+    AppError::NoError
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ok() {
+        assert_eq!(_test_ok_(), DriverError::NoError);
+        assert_eq!(_test_sel4_(Error::NoError), DriverError::NoError);
+        assert_eq!(_test_driver_(DriverError::NoError), AppError::NoError);
+    }
+
+    #[test]
+    fn test_sel4() {
+        assert_eq!(
+            _test_sel4_(Error::NotEnoughMemory),
+            DriverError::NotEnoughMemory
+        );
+        assert_eq!(
+            DriverError::NotEnoughMemory as i32,
+            Error::NotEnoughMemory as i32
+        );
+    }
+
+    #[test]
+    fn test_layers() {
+        let app_error = _test_driver_(DriverError::Timeout);
+        assert_eq!(app_error, AppError::Timeout);
+        assert_eq!(app_error as i32, 1101);
+        assert_eq!(_test_two_layers_(Error::RangeError) as i32, 1004);
+        assert_eq!(_test_own_(), Err(JustDriverError::Timeout));
+    }
+
+    #[test]
+    fn test_unwrap_layer() {
+        assert_eq!(
+            JustDriverError::try_from(JustAppError::DeviceBusy),
+            Ok(JustDriverError::DeviceBusy)
+        );
+        assert_eq!(
+            JustDriverError::try_from(JustAppError::BadConfig),
+            Err(JustAppError::BadConfig)
+        );
+        assert_eq!(
+            JustError::try_from(JustDriverError::FailedLookup),
+            Ok(JustError::FailedLookup)
+        );
+    }
+
+    #[test]
+    fn test_try_from() {
+        assert_eq!(AppError::try_from(0), Ok(AppError::NoError));
+        assert_eq!(AppError::try_from(1), Ok(AppError::BadConfig));
+        assert_eq!(AppError::try_from(1100), Ok(AppError::DeviceBusy));
+        assert_eq!(AppError::try_from(2), Err(2));
+        assert_eq!(DriverError::try_from(102), Err(102));
+    }
+}
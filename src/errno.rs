@@ -0,0 +1,340 @@
+use crate::intersection;
+use crate::intersection::JustError;
+use core::fmt::Debug;
+use core::result::Result as CoreResult;
+use enum_extra::NonZeroRepr;
+use strum::{EnumCount, EnumMetadata};
+
+//
+// errno, done the same way as intersection::Error, with impl_intersection!.
+//
+// Errno is the C compatible errno (with 0 for no error), and JustErrno is the errors.
+// The values are the ones from linux's asm-generic/errno-base.h, which are the
+// ones musl uses on nearly every architecture. Only errno-base is covered so that
+// the discriminants stay contiguous.
+//
+// The mapping from seL4 errors is lossy in both directions, e.g. InvalidArgument,
+// TruncatedMessage and AlignmentError are all EINVAL, and EINVAL comes back as InvalidArgument.
+// If that mapping isn't the one you want, implement Mapping and use the *_with functions,
+// the From impls all use DefaultMapping, and so does `?` on an seL4 error in a function
+// returning Errno, through JustErrno's From<JustError>.
+//
+
+crate::impl_intersection!(Errno, JustErrno, i32);
+
+impl From<JustErrno> for Errno {
+    fn from(it: JustErrno) -> Self {
+        let error_code = it as i32;
+        // Safety: All JustErrno error codes are valid and equivalent Errno error codes.
+        unsafe { core::mem::transmute(error_code) }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumCount, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(i32)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Errno {
+    NoError = 0,
+    EPERM,
+    ENOENT,
+    ESRCH,
+    EINTR,
+    EIO,
+    ENXIO,
+    E2BIG,
+    ENOEXEC,
+    EBADF,
+    ECHILD,
+    EAGAIN,
+    ENOMEM,
+    EACCES,
+    EFAULT,
+    ENOTBLK,
+    EBUSY,
+    EEXIST,
+    EXDEV,
+    ENODEV,
+    ENOTDIR,
+    EISDIR,
+    EINVAL,
+    ENFILE,
+    EMFILE,
+    ENOTTY,
+    ETXTBSY,
+    EFBIG,
+    ENOSPC,
+    ESPIPE,
+    EROFS,
+    EMLINK,
+    EPIPE,
+    EDOM,
+    ERANGE,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, EnumMetadata, NonZeroRepr)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(i32)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum JustErrno {
+    EPERM = 1,
+    ENOENT,
+    ESRCH,
+    EINTR,
+    EIO,
+    ENXIO,
+    E2BIG,
+    ENOEXEC,
+    EBADF,
+    ECHILD,
+    EAGAIN,
+    ENOMEM,
+    EACCES,
+    EFAULT,
+    ENOTBLK,
+    EBUSY,
+    EEXIST,
+    EXDEV,
+    ENODEV,
+    ENOTDIR,
+    EISDIR,
+    EINVAL,
+    ENFILE,
+    EMFILE,
+    ENOTTY,
+    ETXTBSY,
+    EFBIG,
+    ENOSPC,
+    ESPIPE,
+    EROFS,
+    EMLINK,
+    EPIPE,
+    EDOM,
+    ERANGE,
+}
+
+// Compile time check that JustErrno is one less than Errno.
+const _: () = if Errno::COUNT == JustErrno::COUNT + 1 {
+} else {
+    panic!()
+};
+
+// Compile time check that the discriminants are contiguous, TryFrom<i32> relies on it.
+const _: () = if Errno::ERANGE as usize + 1 == Errno::COUNT {
+} else {
+    panic!()
+};
+
+pub trait Mapping {
+    fn to_errno(error: JustError) -> JustErrno;
+    fn from_errno(errno: JustErrno) -> JustError;
+}
+
+pub struct DefaultMapping;
+
+impl Mapping for DefaultMapping {
+    fn to_errno(error: JustError) -> JustErrno {
+        match error {
+            JustError::InvalidArgument => JustErrno::EINVAL,
+            JustError::InvalidCapability => JustErrno::EBADF,
+            JustError::IllegalOperation => JustErrno::EPERM,
+            JustError::RangeError => JustErrno::ERANGE,
+            JustError::AlignmentError => JustErrno::EINVAL,
+            JustError::FailedLookup => JustErrno::EBADF,
+            JustError::TruncatedMessage => JustErrno::EINVAL,
+            JustError::DeleteFirst => JustErrno::EEXIST,
+            JustError::RevokeFirst => JustErrno::EBUSY,
+            JustError::NotEnoughMemory => JustErrno::ENOMEM,
+        }
+    }
+
+    // Anything without an obvious counterpart is an IllegalOperation.
+    fn from_errno(errno: JustErrno) -> JustError {
+        match errno {
+            JustErrno::EINVAL => JustError::InvalidArgument,
+            JustErrno::EBADF => JustError::FailedLookup,
+            JustErrno::ERANGE | JustErrno::EDOM | JustErrno::EFBIG => JustError::RangeError,
+            JustErrno::EEXIST => JustError::DeleteFirst,
+            JustErrno::EBUSY => JustError::RevokeFirst,
+            JustErrno::ENOMEM | JustErrno::ENOSPC => JustError::NotEnoughMemory,
+            JustErrno::ENOENT | JustErrno::ENXIO | JustErrno::ENODEV => JustError::FailedLookup,
+            _ => JustError::IllegalOperation,
+        }
+    }
+}
+
+pub fn to_errno_with<M: Mapping>(error: intersection::Error) -> Errno {
+    match CoreResult::<(), JustError>::from(error) {
+        Ok(()) => Errno::NoError,
+        Err(error) => Errno::from(M::to_errno(error)),
+    }
+}
+
+pub fn from_errno_with<M: Mapping>(errno: Errno) -> intersection::Error {
+    match CoreResult::<(), JustErrno>::from(errno) {
+        Ok(()) => intersection::Error::NoError,
+        // Not through Error's FromResidual, this isn't a `?`, so it leaves no trace frame.
+        // Safety: JustError's discriminants are all Error's, see intersection.rs.
+        Err(errno) => unsafe {
+            core::mem::transmute::<JustError, intersection::Error>(M::from_errno(errno))
+        },
+    }
+}
+
+impl From<JustError> for JustErrno {
+    fn from(error: JustError) -> Self {
+        DefaultMapping::to_errno(error)
+    }
+}
+
+impl From<JustErrno> for JustError {
+    fn from(errno: JustErrno) -> Self {
+        DefaultMapping::from_errno(errno)
+    }
+}
+
+impl From<intersection::Error> for Errno {
+    fn from(error: intersection::Error) -> Self {
+        to_errno_with::<DefaultMapping>(error)
+    }
+}
+
+impl From<Errno> for intersection::Error {
+    fn from(errno: Errno) -> Self {
+        from_errno_with::<DefaultMapping>(errno)
+    }
+}
+
+#[test]
+fn test_count() {
+    assert_eq!(Errno::COUNT as usize, Errno::ERANGE as usize + 1)
+}
+
+fn _test_ok_() -> Errno {
+    let () = Errno::NoError?;
+    Errno::NoError
+}
+
+fn _test_einval_() -> CoreResult<(), JustErrno> {
+    Ok(Errno::EINVAL?)
+}
+
+// What the posix layer looks like.
+fn _test_posix_(error: intersection::Error) -> Errno {
+    error?;
+    Errno::NoError
+}
+
+fn _test_sel4_(errno: Errno) -> CoreResult<(), JustError> {
+    Ok(errno?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intersection::Error;
+
+    struct StrictMapping;
+
+    impl Mapping for StrictMapping {
+        fn to_errno(error: JustError) -> JustErrno {
+            match error {
+                JustError::NotEnoughMemory => JustErrno::ENOMEM,
+                _ => JustErrno::EIO,
+            }
+        }
+
+        fn from_errno(_errno: JustErrno) -> JustError {
+            JustError::IllegalOperation
+        }
+    }
+
+    #[test]
+    fn test_ok() {
+        assert_eq!(_test_ok_(), Errno::NoError);
+        assert_eq!(_test_posix_(Error::NoError), Errno::NoError);
+        assert_eq!(_test_sel4_(Errno::NoError), Ok(()));
+    }
+
+    #[test]
+    fn test_einval() {
+        assert_eq!(_test_einval_(), Err(JustErrno::EINVAL));
+    }
+
+    #[test]
+    fn test_posix() {
+        assert_eq!(_test_posix_(Error::InvalidArgument), Errno::EINVAL);
+        assert_eq!(_test_posix_(Error::NotEnoughMemory), Errno::ENOMEM);
+        assert_eq!(_test_posix_(Error::FailedLookup), Errno::EBADF);
+        assert_eq!(_test_posix_(Error::RangeError), Errno::ERANGE);
+    }
+
+    #[test]
+    fn test_sel4() {
+        assert_eq!(_test_sel4_(Errno::EINVAL), Err(JustError::InvalidArgument));
+        assert_eq!(_test_sel4_(Errno::ENOMEM), Err(JustError::NotEnoughMemory));
+        assert_eq!(_test_sel4_(Errno::EBADF), Err(JustError::FailedLookup));
+        assert_eq!(_test_sel4_(Errno::ERANGE), Err(JustError::RangeError));
+        assert_eq!(_test_sel4_(Errno::EINTR), Err(JustError::IllegalOperation));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for error_code in 0..Error::COUNT as i32 {
+            let error = Error::try_from(error_code).unwrap();
+            let errno = Errno::from(error);
+            assert_eq!(errno == Errno::NoError, error == Error::NoError);
+            // Lossy, but it should always land on something which maps back to the same errno.
+            assert_eq!(Errno::from(Error::from(errno)), errno);
+        }
+    }
+
+    #[test]
+    fn test_override() {
+        assert_eq!(
+            to_errno_with::<StrictMapping>(Error::RangeError),
+            Errno::EIO
+        );
+        assert_eq!(
+            to_errno_with::<StrictMapping>(Error::NotEnoughMemory),
+            Errno::ENOMEM
+        );
+        assert_eq!(
+            to_errno_with::<StrictMapping>(Error::NoError),
+            Errno::NoError
+        );
+        assert_eq!(
+            from_errno_with::<StrictMapping>(Errno::ENOMEM),
+            Error::IllegalOperation
+        );
+    }
+
+    #[test]
+    fn test_combinators() {
+        assert!(Errno::NoError.is_ok());
+        assert_eq!(Errno::EIO.err(), Some(JustErrno::EIO));
+        assert_eq!(
+            Errno::EPERM.map_err(JustError::from),
+            Err(JustError::IllegalOperation)
+        );
+    }
+
+    #[test]
+    fn test_from_errno_no_trace() {
+        crate::trace::clear();
+        crate::last_error::clear();
+        assert_eq!(Error::from(Errno::ENOMEM), Error::NotEnoughMemory);
+        assert!(crate::trace::last_error().is_empty(), "not a `?`");
+        assert_eq!(crate::last_error::get(), 0);
+    }
+
+    #[test]
+    fn test_try_from() {
+        assert_eq!(Errno::try_from(22), Ok(Errno::EINVAL));
+        assert_eq!(
+            Errno::try_from(Errno::COUNT as i32),
+            Err(Errno::COUNT as i32)
+        );
+        assert_eq!(Errno::try_from(-1), Err(-1));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::intersection::{Error, JustError};
+use core::convert::Infallible;
+use core::ops::{ControlFlow, FromResidual, Try};
+use core::result::Result as CoreResult;
+
+//
+// The invocations which return a value as well as an error, as a struct like
+//
+// typedef struct seL4_X86_IOPort_In8 {
+//     int error;
+//     seL4_Uint8 result;
+// } seL4_X86_IOPort_In8_t;
+//
+// ErrorAnd<T> is that struct, with an intersection::Error for the error,
+// so `?` gives you the T, or returns the JustError like `?` on an Error would.
+//
+// Like the kernel, the result is T::default() when there is an error, it isn't looked at
+// by anything on the Rust side, but the C side gets something initialized.
+//
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(C)]
+pub struct ErrorAnd<T> {
+    pub error: Error,
+    pub result: T,
+}
+
+impl<T> ErrorAnd<T> {
+    pub const fn new(error: Error, result: T) -> Self {
+        ErrorAnd { error, result }
+    }
+}
+
+impl<T: Default> Try for ErrorAnd<T> {
+    type Output = T;
+    type Residual = CoreResult<Infallible, JustError>;
+
+    fn branch(self) -> ControlFlow<Self::Residual, T> {
+        match CoreResult::<(), JustError>::from(self.error) {
+            Ok(()) => ControlFlow::Continue(self.result),
+            Err(just_error) => ControlFlow::Break(Err(just_error)),
+        }
+    }
+
+    fn from_output(result: T) -> Self {
+        ErrorAnd::new(Error::NoError, result)
+    }
+}
+
+// The same residuals as intersection::Error, so `?` goes either way between them.
+impl<T: Default, E: Into<JustError>> FromResidual<CoreResult<Infallible, E>> for ErrorAnd<T> {
+    #[track_caller]
+    fn from_residual(result: CoreResult<Infallible, E>) -> Self {
+        ErrorAnd::new(Error::from_residual(result), T::default())
+    }
+}
+
+impl<T> From<ErrorAnd<T>> for CoreResult<T, JustError> {
+    fn from(it: ErrorAnd<T>) -> Self {
+        CoreResult::<(), JustError>::from(it.error).map(|()| it.result)
+    }
+}
+
+impl<T: Default> From<CoreResult<T, JustError>> for ErrorAnd<T> {
+    fn from(result: CoreResult<T, JustError>) -> Self {
+        match result {
+            Ok(output) => ErrorAnd::from_output(output),
+            Err(just_error) => ErrorAnd::from_residual(Err::<Infallible, _>(just_error)),
+        }
+    }
+}
+
+// What the C side declares, to check ErrorAnd against.
+#[allow(non_camel_case_types)]
+#[repr(C)]
+struct seL4_ARM_Page_GetAddress_t {
+    error: i32,
+    paddr: usize,
+}
+
+#[allow(non_camel_case_types)]
+#[repr(C)]
+struct seL4_X86_IOPort_In8_t {
+    error: i32,
+    result: u8,
+}
+
+// Compile time check that ErrorAnd is the same size and alignment as the C structs.
+const _: () = if core::mem::size_of::<ErrorAnd<usize>>()
+    == core::mem::size_of::<seL4_ARM_Page_GetAddress_t>()
+    && core::mem::align_of::<ErrorAnd<usize>>()
+        == core::mem::align_of::<seL4_ARM_Page_GetAddress_t>()
+    && core::mem::size_of::<ErrorAnd<u8>>() == core::mem::size_of::<seL4_X86_IOPort_In8_t>()
+    && core::mem::align_of::<ErrorAnd<u8>>() == core::mem::align_of::<seL4_X86_IOPort_In8_t>()
+{
+} else {
+    panic!()
+};
+
+// seL4_ARM_Page_GetAddress, with the physical address of the page.
+fn _get_address_(error: Error) -> ErrorAnd<usize> {
+    ErrorAnd::new(error, if error == Error::NoError { 0x8000 } else { 0 })
+}
+
+fn _test_ok_() -> ErrorAnd<usize> {
+    let paddr = _get_address_(Error::NoError)?;
+    ErrorAnd::from_output(paddr + 0x10)
+}
+
+fn _test_err_() -> ErrorAnd<usize> {
+    let paddr = _get_address_(Error::InvalidCapability)?;
+    ErrorAnd::from_output(paddr + 0x10)
+}
+
+// `?` on an ErrorAnd in a function returning just the Error.
+fn _test_error_() -> Error {
+    let _paddr = _get_address_(Error::FailedLookup)?;
+    Error::NoError
+}
+
+// `?` on an Error in a function returning an ErrorAnd.
+fn _test_from_error_() -> ErrorAnd<u8> {
+    Error::RangeError?;
+    ErrorAnd::from_output(1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::ptr::addr_of;
+
+    #[test]
+    fn test_ok() {
+        assert_eq!(_test_ok_(), ErrorAnd::new(Error::NoError, 0x8010));
+    }
+
+    #[test]
+    fn test_err() {
+        assert_eq!(_test_err_(), ErrorAnd::new(Error::InvalidCapability, 0));
+    }
+
+    #[test]
+    fn test_error() {
+        assert_eq!(_test_error_(), Error::FailedLookup);
+        assert_eq!(_test_from_error_(), ErrorAnd::new(Error::RangeError, 0));
+    }
+
+    #[test]
+    fn test_core_result() {
+        assert_eq!(CoreResult::from(_test_ok_()), Ok(0x8010));
+        assert_eq!(
+            CoreResult::from(_test_err_()),
+            Err(JustError::InvalidCapability)
+        );
+        assert_eq!(
+            ErrorAnd::from(Ok::<u8, JustError>(7)),
+            ErrorAnd::new(Error::NoError, 7)
+        );
+        assert_eq!(
+            ErrorAnd::<u8>::from(Err(JustError::DeleteFirst)),
+            ErrorAnd::new(Error::DeleteFirst, 0)
+        );
+    }
+
+    #[test]
+    fn test_layout() {
+        let it = ErrorAnd::new(Error::NoError, 0_usize);
+        let c = seL4_ARM_Page_GetAddress_t { error: 0, paddr: 0 };
+        let offset = addr_of!(it.result) as usize - addr_of!(it) as usize;
+        let c_offset = addr_of!(c.paddr) as usize - addr_of!(c) as usize;
+        assert_eq!(offset, c_offset);
+        assert_eq!(addr_of!(it.error) as usize, addr_of!(it) as usize);
+        // Safety: ErrorAnd<usize> and seL4_ARM_Page_GetAddress_t are the same size.
+        let c = unsafe { core::mem::transmute::<_, seL4_ARM_Page_GetAddress_t>(_test_err_()) };
+        assert_eq!(c.error, Error::InvalidCapability as i32);
+        assert_eq!(c.paddr, 0);
+    }
+}
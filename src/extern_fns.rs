@@ -0,0 +1,236 @@
+//
+// Result returning wrappers for imported C functions which return an error code,
+//
+// c_error_fns! {
+//     pub mod raw;
+//     unsafe extern "C" {
+//         safe fn seL4_CNode_Delete(service: CPtr, index: Word, depth: u8) -> Error;
+//         safe fn seL4_ARM_Page_GetAddress(service: CPtr; paddr: *mut Word) -> Error;
+//     }
+// }
+//
+// declares the functions as they are in raw::, and next to it a function of the same name
+// returning Result<(), JustError>. The out params come after the `;`, the wrapper passes
+// pointers to uninitialized memory for them, and returns what was written there,
+// seL4_ARM_Page_GetAddress(service) -> Result<Word, JustError>, or a tuple for more than one.
+//
+// The error type is whatever the function is declared as returning, any Strategy,
+// the Result's error is its Strategy::Error, e.g. a union::Error for a union::Error.
+//
+// Safety is per function, as it is for RFC 3484's unsafe extern blocks. A wrapper is an
+// unsafe fn with the same contract as the raw one, unless the function is a `safe fn`,
+// which is where the caller says it is safe to call with any value of its arguments, and
+// writes all of its out params when it succeeds, as the seL4 invocations do.
+// `unsafe fn` is the default said out loud. As in the RFC, `safe fn` is only allowed in
+// an `unsafe extern` block, whose unsafe is the promise that the declarations are right.
+// The raw functions are unsafe to call either way.
+//
+// It is macro_rules! rather than a #[c_error_fn] attribute, as an attribute needs a
+// proc-macro crate of its own, and this repo is the one crate.
+//
+
+#[macro_export]
+macro_rules! c_error_fns {
+    (
+        $raw_vis:vis mod $raw:ident;
+        $(#[$meta:meta])*
+        unsafe extern $abi:literal { $($fns:tt)* }
+    ) => {
+        $crate::c_error_fns!(@fns [unsafe extern $raw_vis mod $raw; $(#[$meta])* $abi] [] $($fns)*);
+    };
+    (
+        $raw_vis:vis mod $raw:ident;
+        $(#[$meta:meta])*
+        extern $abi:literal { $($fns:tt)* }
+    ) => {
+        $crate::c_error_fns!(@fns [extern $raw_vis mod $raw; $(#[$meta])* $abi] [] $($fns)*);
+    };
+    // One function at a time, into [safety [metas] vis name (args) error].
+    (@fns [extern $($block:tt)*] [$($fns:tt)*] $(#[$fn_meta:meta])* $vis:vis safe fn $($rest:tt)*) => {
+        compile_error!("safe fn is only allowed in an unsafe extern block");
+    };
+    (@fns $block:tt [$($fns:tt)*] $(#[$fn_meta:meta])* $vis:vis safe fn $name:ident $args:tt -> $error:ty; $($rest:tt)*) => {
+        $crate::c_error_fns!(@fns $block [$($fns)* [[] [$(#[$fn_meta])*] $vis $name $args $error]] $($rest)*);
+    };
+    (@fns $block:tt [$($fns:tt)*] $(#[$fn_meta:meta])* $vis:vis unsafe fn $name:ident $args:tt -> $error:ty; $($rest:tt)*) => {
+        $crate::c_error_fns!(@fns $block [$($fns)* [[unsafe] [$(#[$fn_meta])*] $vis $name $args $error]] $($rest)*);
+    };
+    (@fns $block:tt [$($fns:tt)*] $(#[$fn_meta:meta])* $vis:vis fn $name:ident $args:tt -> $error:ty; $($rest:tt)*) => {
+        $crate::c_error_fns!(@fns $block [$($fns)* [[unsafe] [$(#[$fn_meta])*] $vis $name $args $error]] $($rest)*);
+    };
+    (@fns [unsafe extern $($block:tt)*] [$($fns:tt)*]) => {
+        $crate::c_error_fns!(@block [$($block)*] $($fns)*);
+    };
+    (@fns [extern $($block:tt)*] [$($fns:tt)*]) => {
+        $crate::c_error_fns!(@block [$($block)*] $($fns)*);
+    };
+    (
+        @block [$raw_vis:vis mod $raw:ident; $(#[$meta:meta])* $abi:literal]
+        $(
+            [
+                $unsafe:tt
+                [$(#[$fn_meta:meta])*]
+                $vis:vis $name:ident(
+                    $($arg:ident: $arg_ty:ty),* $(,)?
+                    $(; $($out:ident: *mut $out_ty:ty),+ $(,)?)?
+                )
+                $error:ty
+            ]
+        )*
+    ) => {
+        $raw_vis mod $raw {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $(#[$meta])*
+            extern $abi {
+                $(
+                    $(#[$fn_meta])*
+                    pub fn $name($($arg: $arg_ty,)* $($($out: *mut $out_ty),+)?) -> $error;
+                )*
+            }
+        }
+
+        $(
+            $crate::c_error_fns!(
+                @wrapper $unsafe, $raw, $vis, $name, [$($arg: $arg_ty),*], [$($($out: $out_ty),+)?], $error
+            );
+        )*
+    };
+    (@wrapper [$($unsafe:tt)?], $raw:ident, $vis:vis, $name:ident, [$($arg:ident: $arg_ty:ty),*], [], $error:ty) => {
+        #[allow(unused_unsafe)]
+        $vis $($unsafe)? fn $name(
+            $($arg: $arg_ty),*
+        ) -> ::core::result::Result<(), <$error as $crate::strategy::Strategy>::Error> {
+            // Safety: Either it is a safe fn, and whoever wrote that says it is safe to call
+            // with any arguments, or the wrapper is unsafe and it is up to its caller.
+            $crate::strategy::Strategy::into_result(unsafe { $raw::$name($($arg),*) })
+        }
+    };
+    (@wrapper [$($unsafe:tt)?], $raw:ident, $vis:vis, $name:ident, [$($arg:ident: $arg_ty:ty),*], [$out:ident: $out_ty:ty], $error:ty) => {
+        #[allow(unused_unsafe)]
+        $vis $($unsafe)? fn $name(
+            $($arg: $arg_ty),*
+        ) -> ::core::result::Result<$out_ty, <$error as $crate::strategy::Strategy>::Error> {
+            // Safety: As above, and the same goes for writing the out param when it succeeds.
+            unsafe { $crate::out_param::call_with_out(|$out| $raw::$name($($arg,)* $out)) }
+        }
+    };
+    (@wrapper [$($unsafe:tt)?], $raw:ident, $vis:vis, $name:ident, [$($arg:ident: $arg_ty:ty),*], [$($out:ident: $out_ty:ty),+], $error:ty) => {
+        #[allow(unused_unsafe)]
+        $vis $($unsafe)? fn $name(
+            $($arg: $arg_ty),*
+        ) -> ::core::result::Result<($($out_ty,)+), <$error as $crate::strategy::Strategy>::Error> {
+            // Safety: As above, and the same goes for writing all of the out params when it succeeds.
+            unsafe {
+                $crate::out_param::call_with_outs(|($($out,)+)| $raw::$name($($arg,)* $($out),+))
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::intersection::{Error, JustError};
+    use crate::union;
+
+    type Word = usize;
+
+    // Stands in for the C side.
+    mod c {
+        use super::*;
+
+        #[no_mangle]
+        extern "C" fn c_error_fns_delete(index: Word, depth: u8) -> Error {
+            if depth > 32 || index >> depth != 0 {
+                Error::RangeError
+            } else {
+                Error::NoError
+            }
+        }
+
+        #[no_mangle]
+        unsafe extern "C" fn c_error_fns_get_address(vaddr: Word, paddr: *mut Word) -> Error {
+            if vaddr % 0x1000 != 0 {
+                return Error::AlignmentError;
+            }
+            paddr.write(vaddr + 0x8000);
+            Error::NoError
+        }
+
+        #[no_mangle]
+        unsafe extern "C" fn c_error_fns_split(
+            word: Word,
+            high: *mut u16,
+            low: *mut u16,
+        ) -> union::Error {
+            high.write((word >> 16) as u16);
+            low.write(word as u16);
+            union::Error::NoError
+        }
+
+        #[no_mangle]
+        unsafe extern "C" fn c_error_fns_read(address: *const Word, value: *mut Word) -> Error {
+            if address.is_null() {
+                return Error::InvalidArgument;
+            }
+            value.write(address.read());
+            Error::NoError
+        }
+    }
+
+    crate::c_error_fns! {
+        mod raw;
+        unsafe extern "C" {
+            safe fn c_error_fns_delete(index: Word, depth: u8) -> Error;
+            safe fn c_error_fns_get_address(vaddr: Word; paddr: *mut Word) -> Error;
+            safe fn c_error_fns_split(word: Word; high: *mut u16, low: *mut u16) -> union::Error;
+            // Not safe with any argument, so the wrapper is an unsafe fn.
+            fn c_error_fns_read(address: *const Word; value: *mut Word) -> Error;
+        }
+    }
+
+    // Without the unsafe on the block, everything in it is unsafe.
+    mod plain {
+        use super::*;
+
+        crate::c_error_fns! {
+            mod raw;
+            extern "C" {
+                pub unsafe fn c_error_fns_read(address: *const Word; value: *mut Word) -> Error;
+            }
+        }
+    }
+
+    #[test]
+    fn test_wrapper() {
+        assert_eq!(c_error_fns_delete(3, 2), Ok(()));
+        assert_eq!(c_error_fns_delete(4, 2), Err(JustError::RangeError));
+    }
+
+    #[test]
+    fn test_out() {
+        assert_eq!(c_error_fns_get_address(0x2000), Ok(0xa000));
+        assert_eq!(
+            c_error_fns_get_address(0x2001),
+            Err(JustError::AlignmentError)
+        );
+        assert_eq!(c_error_fns_split(0x1234_5678), Ok((0x1234, 0x5678)));
+    }
+
+    #[test]
+    fn test_unsafe() {
+        let word: Word = 42;
+        assert_eq!(unsafe { c_error_fns_read(&word) }, Ok(42));
+        assert_eq!(
+            unsafe { c_error_fns_read(core::ptr::null()) },
+            Err(JustError::InvalidArgument)
+        );
+        assert_eq!(unsafe { plain::c_error_fns_read(&word) }, Ok(42));
+    }
+
+    #[test]
+    fn test_raw() {
+        assert_eq!(unsafe { raw::c_error_fns_delete(4, 2) }, Error::RangeError);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::{intersection, union};
+use core::sync::atomic::{AtomicI32, AtomicU32, AtomicU8, Ordering};
+
+//
+// Fault injection, for making the rarely taken `?` branches actually get taken.
+//
+// A call site is wrapped in fault_point!(site, expr) where site is any u32 the caller
+// likes, when armed the Error produced by expr is replaced with the armed error code.
+// Without the fault-injection feature (and outside of this crate's tests)
+// fault_point! is just expr.
+//
+// The configuration is a handful of static atomics so it works without std,
+// but arming is not atomic as a whole, so arm before starting anything which might
+// reach a fault point, and don't arm from more than one place at a time.
+//
+
+// Used as the site to make every fault point eligible.
+pub const ANY_SITE: u32 = u32::MAX;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum When {
+    // Every time an eligible site is reached.
+    Always,
+    // Only the nth time an eligible site is reached, counting from 1.
+    Nth(u32),
+    // On average once in every one_in times an eligible site is reached,
+    // the same seed gives the same sequence of faults.
+    Random { seed: u32, one_in: u32 },
+}
+
+const DISARMED: u8 = 0;
+const ALWAYS: u8 = 1;
+const NTH: u8 = 2;
+const RANDOM: u8 = 3;
+
+static MODE: AtomicU8 = AtomicU8::new(DISARMED);
+static SITE: AtomicU32 = AtomicU32::new(ANY_SITE);
+// The n for When::Nth, or one_in for When::Random.
+static N: AtomicU32 = AtomicU32::new(0);
+static HITS: AtomicU32 = AtomicU32::new(0);
+static STATE: AtomicU32 = AtomicU32::new(0);
+static ERROR_CODE: AtomicI32 = AtomicI32::new(0);
+
+pub fn arm(site: u32, when: When, error_code: i32) {
+    MODE.store(DISARMED, Ordering::SeqCst);
+    let (mode, n) = match when {
+        When::Always => (ALWAYS, 0),
+        When::Nth(n) => (NTH, n),
+        When::Random { seed, one_in } => {
+            // xorshift never leaves 0.
+            STATE.store(
+                if seed == 0 { 0x9e37_79b9 } else { seed },
+                Ordering::Relaxed,
+            );
+            (RANDOM, one_in.max(1))
+        }
+    };
+    SITE.store(site, Ordering::Relaxed);
+    N.store(n, Ordering::Relaxed);
+    HITS.store(0, Ordering::Relaxed);
+    ERROR_CODE.store(error_code, Ordering::Relaxed);
+    MODE.store(mode, Ordering::Release);
+}
+
+pub fn disarm() {
+    MODE.store(DISARMED, Ordering::SeqCst);
+}
+
+// The number of times an eligible site has been reached since arming.
+pub fn hits() -> u32 {
+    HITS.load(Ordering::Relaxed)
+}
+
+fn xorshift(mut x: u32) -> u32 {
+    x ^= x << 13;
+    x ^= x >> 17;
+    x ^= x << 5;
+    x
+}
+
+// Some(error_code) if the fault point at site should fail.
+pub fn fire(site: u32) -> Option<i32> {
+    let mode = MODE.load(Ordering::Acquire);
+    if mode == DISARMED {
+        return None;
+    }
+    let armed_site = SITE.load(Ordering::Relaxed);
+    if armed_site != ANY_SITE && armed_site != site {
+        return None;
+    }
+    let hit = HITS.fetch_add(1, Ordering::Relaxed).wrapping_add(1);
+    let fire = match mode {
+        ALWAYS => true,
+        NTH => hit == N.load(Ordering::Relaxed),
+        RANDOM => {
+            let state = STATE
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| Some(xorshift(x)))
+                .unwrap_or_else(|x| x);
+            xorshift(state) % N.load(Ordering::Relaxed) == 0
+        }
+        _ => false,
+    };
+    if fire {
+        Some(ERROR_CODE.load(Ordering::Relaxed))
+    } else {
+        None
+    }
+}
+
+pub trait Inject: Sized {
+    // self, unless the fault point at site fires.
+    //
+    // An armed error code which isn't valid for Self leaves self alone.
+    fn inject(self, site: u32) -> Self;
+}
+
+impl Inject for intersection::Error {
+    fn inject(self, site: u32) -> Self {
+        match fire(site) {
+            Some(error_code) => Self::try_from(error_code).unwrap_or(self),
+            None => self,
+        }
+    }
+}
+
+impl Inject for union::Error {
+    fn inject(self, site: u32) -> Self {
+        match fire(site) {
+            Some(error_code) => Self::try_from(error_code).unwrap_or(self),
+            None => self,
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! fault_point {
+    ($site:expr, $error:expr) => {
+        $crate::fault::Inject::inject($error, $site)
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intersection::{Error, JustError};
+    use std::lazy::SyncLazy;
+    use std::sync::{Mutex, MutexGuard, PoisonError};
+    use strum::EnumCount;
+
+    // The configuration is global, and the tests run in parallel.
+    // Sites used here are all above 1000 so they don't upset any other tests.
+    static LOCK: SyncLazy<Mutex<()>> = SyncLazy::new(|| Mutex::new(()));
+
+    // A test which failed holding it doesn't stop the rest, each of them arms or disarms first.
+    fn lock() -> MutexGuard<'static, ()> {
+        LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    const SITE_A: u32 = 1001;
+    const SITE_B: u32 = 1002;
+
+    fn _step_(site: u32) -> Error {
+        fault_point!(site, Error::NoError)
+    }
+
+    fn _three_steps_(site: u32) -> Result<(), JustError> {
+        _step_(site)?;
+        _step_(site)?;
+        _step_(site)?;
+        Ok(())
+    }
+
+    fn _union_step_(site: u32) -> union::Error {
+        fault_point!(site, union::Error::NoError)?;
+        union::Error::NoError
+    }
+
+    #[test]
+    fn test_disarmed() {
+        let _lock = lock();
+        disarm();
+        assert_eq!(_three_steps_(SITE_A), Ok(()));
+    }
+
+    #[test]
+    fn test_site() {
+        let _lock = lock();
+        arm(SITE_A, When::Always, Error::DeleteFirst as i32);
+        assert_eq!(_three_steps_(SITE_A), Err(JustError::DeleteFirst));
+        assert_eq!(_three_steps_(SITE_B), Ok(()));
+        assert_eq!(_union_step_(SITE_A), union::Error::DeleteFirst);
+        disarm();
+    }
+
+    #[test]
+    fn test_nth() {
+        let _lock = lock();
+        arm(SITE_A, When::Nth(3), Error::RevokeFirst as i32);
+        assert_eq!(_three_steps_(SITE_A), Err(JustError::RevokeFirst));
+        assert_eq!(hits(), 3);
+        // Only the third, not every third.
+        assert_eq!(_three_steps_(SITE_A), Ok(()));
+        disarm();
+    }
+
+    #[test]
+    fn test_random_is_reproducible() {
+        let _lock = lock();
+        let mut first = [false; 64];
+        let mut second = [false; 64];
+        for run in [&mut first, &mut second] {
+            arm(
+                SITE_A,
+                When::Random {
+                    seed: 42,
+                    one_in: 4,
+                },
+                1,
+            );
+            for fired in run.iter_mut() {
+                *fired = _step_(SITE_A) != Error::NoError;
+            }
+        }
+        disarm();
+        assert_eq!(first, second);
+        assert!(first.iter().any(|fired| *fired));
+        assert!(first.iter().any(|fired| !*fired));
+    }
+
+    #[test]
+    fn test_invalid_error_code() {
+        let _lock = lock();
+        arm(SITE_A, When::Always, Error::COUNT as i32);
+        assert_eq!(_three_steps_(SITE_A), Ok(()));
+        disarm();
+    }
+}
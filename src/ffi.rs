@@ -0,0 +1,250 @@
+use crate::intersection::{Error, JustError};
+use core::panic::UnwindSafe;
+use core::result::Result as CoreResult;
+
+//
+// Rust functions exported to C,
+//
+// #[no_mangle]
+// pub extern "C" fn driver_reset(device: u32) -> i32 {
+//     ffi_boundary(|| -> Result<(), JustError> {
+//         ...
+//     })
+// }
+//
+// The closure's Ok(()) is 0, an Err is its error code, and a panic doesn't unwind into C.
+//
+// With the std feature (and in the tests) the panic is caught, and the C side gets
+// JustFfiError::Internal, which is seL4's errors plus that one. Without std there's no
+// catch_unwind, a panic which would unwind out of the closure aborts instead.
+//
+// For some other code on a panic, e.g. an error_domain! of your own, implement BoundaryError
+// for its JustError and use ffi_boundary_with::<JustYourError, _, _>.
+//
+// The closure has to be UnwindSafe, like for catch_unwind. After an Internal, whatever it
+// touched may be half updated, so the C side shouldn't expect much of it.
+//
+
+// seL4's errors, and the one for a panic at the boundary.
+crate::error_domain! {
+    pub enum FfiError, JustFfiError: i32 {
+        [JustError at 0] {
+            InvalidArgument,
+            InvalidCapability,
+            IllegalOperation,
+            RangeError,
+            AlignmentError,
+            FailedLookup,
+            TruncatedMessage,
+            DeleteFirst,
+            RevokeFirst,
+            NotEnoughMemory,
+        }
+        Internal = -1,
+    }
+}
+
+// What an ffi_boundary returns to C.
+pub trait BoundaryError: Copy {
+    // The closure's Ok(()).
+    const OK: i32;
+
+    // A panic in the closure.
+    const PANICKED: Self;
+
+    fn error_code(self) -> i32;
+}
+
+impl BoundaryError for JustFfiError {
+    const OK: i32 = FfiError::NoError as i32;
+
+    const PANICKED: Self = JustFfiError::Internal;
+
+    fn error_code(self) -> i32 {
+        self as i32
+    }
+}
+
+pub fn ffi_boundary<E, F>(f: F) -> i32
+where
+    E: Into<JustFfiError>,
+    F: FnOnce() -> CoreResult<(), E> + UnwindSafe,
+{
+    ffi_boundary_with::<JustFfiError, E, F>(f)
+}
+
+pub fn ffi_boundary_with<B, E, F>(f: F) -> i32
+where
+    B: BoundaryError,
+    E: Into<B>,
+    F: FnOnce() -> CoreResult<(), E> + UnwindSafe,
+{
+    match call(f) {
+        Some(Ok(())) => B::OK,
+        Some(Err(error)) => error.into().error_code(),
+        None => B::PANICKED.error_code(),
+    }
+}
+
+// None if f panicked.
+#[cfg(any(test, feature = "std"))]
+fn call<T, F: FnOnce() -> T + UnwindSafe>(f: F) -> Option<T> {
+    std::panic::catch_unwind(f).ok()
+}
+
+#[cfg(not(any(test, feature = "std")))]
+fn call<T, F: FnOnce() -> T + UnwindSafe>(f: F) -> Option<T> {
+    // Only dropped if f unwinds.
+    struct AbortOnUnwind;
+
+    impl Drop for AbortOnUnwind {
+        fn drop(&mut self) {
+            core::intrinsics::abort()
+        }
+    }
+
+    let guard = AbortOnUnwind;
+    let output = f();
+    core::mem::forget(guard);
+    Some(output)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // A driver's own panic code.
+    crate::error_domain! {
+        enum DriverError, JustDriverError: i32 {
+            [JustError at 0] {
+                InvalidArgument,
+                InvalidCapability,
+                IllegalOperation,
+                RangeError,
+                AlignmentError,
+                FailedLookup,
+                TruncatedMessage,
+                DeleteFirst,
+                RevokeFirst,
+                NotEnoughMemory,
+            }
+            DeviceBusy = 100,
+            Wedged = 101,
+        }
+    }
+
+    impl BoundaryError for JustDriverError {
+        const OK: i32 = DriverError::NoError as i32;
+
+        const PANICKED: Self = JustDriverError::Wedged;
+
+        fn error_code(self) -> i32 {
+            self as i32
+        }
+    }
+
+    const TABLE: [u32; 4] = [2, 3, 5, 7];
+
+    // The Rust side, exported to C.
+    mod rust {
+        use super::*;
+
+        #[no_mangle]
+        extern "C" fn ffi_divide(dividend: u32, divisor: u32, quotient: *mut u32) -> i32 {
+            ffi_boundary(|| -> CoreResult<(), JustError> {
+                if divisor == 0 {
+                    Error::InvalidArgument?;
+                }
+                // Safety: The C side passes a valid pointer.
+                unsafe { quotient.write(dividend / divisor) };
+                Ok(())
+            })
+        }
+
+        // Indexing past the end of TABLE panics.
+        #[no_mangle]
+        extern "C" fn ffi_lookup(index: usize, entry: *mut u32) -> i32 {
+            ffi_boundary(|| -> CoreResult<(), JustError> {
+                if index == usize::MAX {
+                    Error::FailedLookup?;
+                }
+                // Safety: The C side passes a valid pointer.
+                unsafe { entry.write(TABLE[index]) };
+                Ok(())
+            })
+        }
+
+        #[no_mangle]
+        extern "C" fn ffi_driver_reset(busy: bool, wedged: bool) -> i32 {
+            ffi_boundary_with::<JustDriverError, _, _>(|| {
+                if wedged {
+                    panic!("wedged");
+                }
+                if busy {
+                    return Err(JustDriverError::DeviceBusy);
+                }
+                Ok(())
+            })
+        }
+    }
+
+    // What the C side declares.
+    extern "C" {
+        fn ffi_divide(dividend: u32, divisor: u32, quotient: *mut u32) -> i32;
+        fn ffi_lookup(index: usize, entry: *mut u32) -> i32;
+        fn ffi_driver_reset(busy: bool, wedged: bool) -> i32;
+    }
+
+    // The C side itself, ffi/test.c, which build.rs compiles with the ffi-c-test feature.
+    #[cfg(ffi_c_test)]
+    #[link(name = "ffi_test", kind = "static")]
+    extern "C" {
+        fn ffi_c_test() -> i32;
+    }
+
+    #[test]
+    fn test_ok() {
+        let mut quotient = 0;
+        assert_eq!(unsafe { ffi_divide(7, 2, &mut quotient) }, 0);
+        assert_eq!(quotient, 3);
+        let mut entry = 0;
+        assert_eq!(unsafe { ffi_lookup(3, &mut entry) }, 0);
+        assert_eq!(entry, 7);
+    }
+
+    #[test]
+    fn test_error() {
+        let mut quotient = 0;
+        assert_eq!(
+            unsafe { ffi_divide(7, 0, &mut quotient) },
+            Error::InvalidArgument as i32
+        );
+        let mut entry = 0;
+        assert_eq!(
+            unsafe { ffi_lookup(usize::MAX, &mut entry) },
+            Error::FailedLookup as i32
+        );
+    }
+
+    #[test]
+    fn test_panic() {
+        let mut entry = 0;
+        assert_eq!(unsafe { ffi_lookup(4, &mut entry) }, -1);
+        assert_eq!(entry, 0);
+        assert_eq!(FfiError::try_from(-1), Ok(FfiError::Internal));
+    }
+
+    #[test]
+    fn test_boundary_with() {
+        assert_eq!(unsafe { ffi_driver_reset(false, false) }, 0);
+        assert_eq!(unsafe { ffi_driver_reset(true, false) }, 100);
+        assert_eq!(unsafe { ffi_driver_reset(true, true) }, 101);
+    }
+
+    #[cfg(ffi_c_test)]
+    #[test]
+    fn test_c() {
+        // 0, or the line of the first check in ffi/test.c which failed.
+        assert_eq!(unsafe { ffi_c_test() }, 0);
+    }
+}
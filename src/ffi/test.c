@@ -0,0 +1,53 @@
+//
+// The C side of ffi.rs's tests, calling the functions the tests export the way
+// a C caller would, and checking the ints they return.
+//
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+int32_t ffi_divide(uint32_t dividend, uint32_t divisor, uint32_t *quotient);
+int32_t ffi_lookup(size_t index, uint32_t *entry);
+int32_t ffi_driver_reset(bool busy, bool wedged);
+
+// JustFfiError's, and JustDriverError's own.
+enum {
+    NoError = 0,
+    InvalidArgument = 1,
+    FailedLookup = 6,
+    Internal = -1,
+    DeviceBusy = 100,
+    Wedged = 101,
+};
+
+#define CHECK(condition)     \
+    do {                     \
+        if (!(condition)) {  \
+            return __LINE__; \
+        }                    \
+    } while (0)
+
+// 0, or the line of the first check which failed.
+int32_t ffi_c_test(void)
+{
+    uint32_t quotient = 0;
+    CHECK(ffi_divide(7, 2, &quotient) == NoError);
+    CHECK(quotient == 3);
+    CHECK(ffi_divide(7, 0, &quotient) == InvalidArgument);
+    CHECK(quotient == 3);
+
+    uint32_t entry = 0;
+    CHECK(ffi_lookup(3, &entry) == NoError);
+    CHECK(entry == 7);
+    CHECK(ffi_lookup(SIZE_MAX, &entry) == FailedLookup);
+    // Past the end of the table, the panic stays on the Rust side.
+    entry = 0;
+    CHECK(ffi_lookup(4, &entry) == Internal);
+    CHECK(entry == 0);
+
+    CHECK(ffi_driver_reset(false, false) == NoError);
+    CHECK(ffi_driver_reset(true, false) == DeviceBusy);
+    CHECK(ffi_driver_reset(true, true) == Wedged);
+    return 0;
+}
@@ -0,0 +1,231 @@
+use crate::intersection::{Error, JustError};
+use core::future::Future;
+use core::pin::Pin;
+use core::result::Result as CoreResult;
+use core::task::{Context, Poll};
+
+//
+// Futures whose output is an intersection::Error, for async executors running on seL4.
+//
+// Nothing here is needed for `?` itself, Error implements Try so
+//
+// async {
+//     retype(..).await?;
+//     Error::NoError
+// }
+//
+// already works, as does `.await?` on a future of Result<_, JustError>.
+// What is here is for the other direction, handing Error futures to code which expects
+// futures of Result, and the reverse, plus a poll_fn since core's isn't stable yet.
+//
+// All of it is no_std and doesn't care which executor polls it.
+//
+
+pub struct PollFn<F> {
+    f: F,
+}
+
+// The future for a function which is polled until it returns Ready.
+pub fn poll_fn<F>(f: F) -> PollFn<F>
+where
+    F: FnMut(&mut Context<'_>) -> Poll<Error>,
+{
+    PollFn { f }
+}
+
+// f isn't structurally pinned, but a closure which isn't Unpin may be a self referential
+// future's state, as it is for core's own PollFn.
+impl<F: Unpin> Unpin for PollFn<F> {}
+
+impl<F> Future for PollFn<F>
+where
+    F: FnMut(&mut Context<'_>) -> Poll<Error>,
+{
+    type Output = Error;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Error> {
+        // Safety: f is only called, never moved out of self.
+        (unsafe { &mut self.get_unchecked_mut().f })(cx)
+    }
+}
+
+// Along the lines of futures::TryFuture, but for a future of Error.
+pub trait TryFuture: Future<Output = Error> {
+    fn try_poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<CoreResult<(), JustError>>;
+
+    fn into_result(self) -> IntoResult<Self>
+    where
+        Self: Sized,
+    {
+        IntoResult { future: self }
+    }
+}
+
+impl<F: Future<Output = Error>> TryFuture for F {
+    fn try_poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<CoreResult<(), JustError>> {
+        self.poll(cx).map(CoreResult::from)
+    }
+}
+
+// A future of Error as a future of Result<(), JustError>.
+pub struct IntoResult<F> {
+    future: F,
+}
+
+impl<F: Future<Output = Error>> Future for IntoResult<F> {
+    type Output = CoreResult<(), JustError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Safety: future is never moved out of self, it is structurally pinned.
+        let future = unsafe { self.map_unchecked_mut(|it| &mut it.future) };
+        future.try_poll(cx)
+    }
+}
+
+// A future of Result<(), JustError> as a future of Error, e.g. for an extern "C" task entry.
+pub struct IntoError<F> {
+    future: F,
+}
+
+pub fn into_error<F>(future: F) -> IntoError<F>
+where
+    F: Future<Output = CoreResult<(), JustError>>,
+{
+    IntoError { future }
+}
+
+impl<F> Future for IntoError<F>
+where
+    F: Future<Output = CoreResult<(), JustError>>,
+{
+    type Output = Error;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Error> {
+        // Safety: future is never moved out of self, it is structurally pinned.
+        let future = unsafe { self.map_unchecked_mut(|it| &mut it.future) };
+        future.poll(cx).map(|result| match result {
+            Ok(()) => Error::NoError,
+            // Not through Error's FromResidual, this isn't a `?`, so it leaves no trace frame.
+            // Safety: JustError's discriminants are all Error's, see intersection.rs.
+            Err(just_error) => unsafe { core::mem::transmute::<JustError, Error>(just_error) },
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::task::{RawWaker, RawWakerVTable, Waker};
+
+    // Enough of an executor to run these, polls until ready without ever sleeping.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        fn clone(_: *const ()) -> RawWaker {
+            RawWaker::new(core::ptr::null(), &VTABLE)
+        }
+        fn noop(_: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+        // Safety: The vtable functions don't use the data pointer at all.
+        let waker = unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &VTABLE)) };
+        let mut cx = Context::from_waker(&waker);
+        let mut future = future;
+        loop {
+            // Safety: future is a local which is never moved once it has been polled.
+            let pinned = unsafe { Pin::new_unchecked(&mut future) };
+            if let Poll::Ready(output) = pinned.poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    // Pending until it has been polled n times, then error.
+    fn _after_(mut n: usize, error: Error) -> impl Future<Output = Error> {
+        poll_fn(move |cx| {
+            if n == 0 {
+                Poll::Ready(error)
+            } else {
+                n -= 1;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        })
+    }
+
+    async fn _steps_(first: Error, second: Error) -> Error {
+        _after_(2, first).await?;
+        _after_(1, second).await?;
+        Error::NoError
+    }
+
+    async fn _result_(error: Error) -> CoreResult<(), JustError> {
+        Ok(_after_(1, error).await?)
+    }
+
+    async fn _mixed_(error: Error) -> Error {
+        _result_(error).await?;
+        Error::NoError
+    }
+
+    #[test]
+    fn test_ok() {
+        assert_eq!(
+            block_on(_steps_(Error::NoError, Error::NoError)),
+            Error::NoError
+        );
+    }
+
+    #[test]
+    fn test_question_mark() {
+        assert_eq!(
+            block_on(_steps_(Error::RangeError, Error::NoError)),
+            Error::RangeError
+        );
+        assert_eq!(
+            block_on(_steps_(Error::NoError, Error::DeleteFirst)),
+            Error::DeleteFirst
+        );
+        assert_eq!(block_on(_mixed_(Error::FailedLookup)), Error::FailedLookup);
+    }
+
+    #[test]
+    fn test_into_result() {
+        assert_eq!(
+            block_on(_after_(3, Error::NotEnoughMemory).into_result()),
+            Err(JustError::NotEnoughMemory)
+        );
+        assert_eq!(block_on(_after_(0, Error::NoError).into_result()), Ok(()));
+    }
+
+    #[test]
+    fn test_into_error() {
+        assert_eq!(
+            block_on(into_error(_result_(Error::RevokeFirst))),
+            Error::RevokeFirst
+        );
+        assert_eq!(
+            block_on(into_error(_result_(Error::NoError))),
+            Error::NoError
+        );
+        crate::trace::clear();
+        crate::last_error::clear();
+        assert_eq!(
+            block_on(into_error(core::future::ready(Err(JustError::DeleteFirst)))),
+            Error::DeleteFirst
+        );
+        assert!(crate::trace::last_error().is_empty(), "not a `?`");
+        assert_eq!(crate::last_error::get(), 0);
+    }
+
+    #[test]
+    fn test_poll_fn_unpin() {
+        fn unpin<T: Unpin>(_: &T) {}
+        let mut polls = 0;
+        let future = poll_fn(|_| {
+            polls += 1;
+            Poll::Ready(Error::NoError)
+        });
+        unpin(&future);
+        assert_eq!(block_on(future), Error::NoError);
+        assert_eq!(polls, 1);
+    }
+}
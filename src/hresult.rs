@@ -0,0 +1,245 @@
+use crate::intersection::{Error, JustError};
+use core::convert::Infallible;
+use core::fmt;
+use core::ops::{ControlFlow, FromResidual, Try};
+use core::result::Result as CoreResult;
+
+//
+// HRESULT style error codes, for the C components which pack everything into a u32.
+//
+//   31        30..=29    28..=27   26..=16    15..=0
+//   severity  reserved   reserved  facility   code
+//             / customer
+//
+// Only the severity bit decides success or failure, so a success can still carry
+// a facility and code (e.g. S_FALSE). `?` keeps the whole HResult on failure,
+// on success it gives back just the code.
+//
+// Each facility is a Rust error enum implementing Facility, which lets `?` on it
+// (or on an intersection::Error, via JustError) return an HResult. The facilities
+// defined here are not Microsoft's, so they are all made with the customer bit set.
+//
+
+pub const SEVERITY: u32 = 1 << 31;
+pub const CUSTOMER: u32 = 1 << 29;
+pub const FACILITY_SHIFT: u32 = 16;
+pub const FACILITY_MASK: u32 = 0x7ff << FACILITY_SHIFT;
+pub const CODE_MASK: u32 = 0xffff;
+
+pub const FACILITY_SEL4: u16 = 0x5e4;
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+#[repr(transparent)]
+pub struct HResult(pub u32);
+
+impl HResult {
+    pub const S_OK: HResult = HResult(0);
+    pub const S_FALSE: HResult = HResult(1);
+
+    pub const fn new(failure: bool, facility: u16, code: u16) -> Self {
+        let severity = if failure { SEVERITY } else { 0 };
+        HResult(
+            severity
+                | CUSTOMER
+                | ((facility as u32) << FACILITY_SHIFT) & FACILITY_MASK
+                | code as u32,
+        )
+    }
+
+    pub const fn is_failure(self) -> bool {
+        self.0 & SEVERITY != 0
+    }
+
+    pub const fn is_success(self) -> bool {
+        !self.is_failure()
+    }
+
+    pub const fn is_customer(self) -> bool {
+        self.0 & CUSTOMER != 0
+    }
+
+    pub const fn facility(self) -> u16 {
+        ((self.0 & FACILITY_MASK) >> FACILITY_SHIFT) as u16
+    }
+
+    pub const fn code(self) -> u16 {
+        (self.0 & CODE_MASK) as u16
+    }
+
+    // The error, if this is a failure from F's facility.
+    pub fn error<F: Facility>(self) -> Option<F> {
+        if self.is_failure() && self.is_customer() && self.facility() == F::FACILITY {
+            F::from_code(self.code())
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Debug for HResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HResult({:#010x})", self.0)
+    }
+}
+
+pub trait Facility: Sized {
+    const FACILITY: u16;
+
+    fn code(self) -> u16;
+    fn from_code(code: u16) -> Option<Self>;
+}
+
+impl Facility for JustError {
+    const FACILITY: u16 = FACILITY_SEL4;
+
+    fn code(self) -> u16 {
+        self as u16
+    }
+
+    fn from_code(code: u16) -> Option<Self> {
+        let error = Error::try_from(code as i32).ok()?;
+        CoreResult::<(), JustError>::from(error).err()
+    }
+}
+
+impl<F: Facility> From<F> for HResult {
+    fn from(error: F) -> Self {
+        HResult::new(true, F::FACILITY, error.code())
+    }
+}
+
+impl Try for HResult {
+    type Output = u16;
+    type Residual = CoreResult<Infallible, HResult>;
+
+    fn branch(self) -> ControlFlow<Self::Residual, Self::Output> {
+        if self.is_failure() {
+            ControlFlow::Break(Err(self))
+        } else {
+            ControlFlow::Continue(self.code())
+        }
+    }
+
+    // A success from no facility in particular.
+    fn from_output(code: u16) -> Self {
+        HResult(code as u32)
+    }
+}
+
+impl FromResidual<CoreResult<Infallible, HResult>> for HResult {
+    fn from_residual(result: CoreResult<Infallible, HResult>) -> Self {
+        match result {
+            // This should never happen because this is along the Continue branch.
+            Ok(_) => HResult::S_OK,
+            Err(hresult) => hresult,
+        }
+    }
+}
+
+// `?` on a facility's error, including intersection::Error.
+impl<F: Facility> FromResidual<CoreResult<Infallible, F>> for HResult {
+    fn from_residual(result: CoreResult<Infallible, F>) -> Self {
+        match result {
+            Ok(_) => HResult::S_OK,
+            Err(error) => HResult::from(error),
+        }
+    }
+}
+
+fn _test_ok_() -> HResult {
+    let _code: u16 = HResult::S_FALSE?;
+    HResult::S_OK
+}
+
+fn _test_sel4_(error: Error) -> HResult {
+    error?;
+    HResult::S_OK
+}
+
+fn _test_core_result_(hresult: HResult) -> CoreResult<u16, HResult> {
+    Ok(hresult?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // A third party component's facility.
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    enum Codec {
+        Corrupt = 1,
+        Unsupported = 2,
+    }
+
+    impl Facility for Codec {
+        const FACILITY: u16 = 0x42;
+
+        fn code(self) -> u16 {
+            self as u16
+        }
+
+        fn from_code(code: u16) -> Option<Self> {
+            match code {
+                1 => Some(Codec::Corrupt),
+                2 => Some(Codec::Unsupported),
+                _ => None,
+            }
+        }
+    }
+
+    fn _decode_(input: CoreResult<(), Codec>) -> HResult {
+        input?;
+        HResult::from_output(7)
+    }
+
+    #[test]
+    fn test_ok() {
+        assert_eq!(_test_ok_(), HResult::S_OK);
+        assert_eq!(_test_sel4_(Error::NoError), HResult::S_OK);
+        assert_eq!(_test_core_result_(HResult::S_FALSE), Ok(1));
+        assert_eq!(_decode_(Ok(())).code(), 7);
+    }
+
+    #[test]
+    fn test_layout() {
+        let hresult = HResult::new(true, 0x7ff, 0xffff);
+        assert_eq!(hresult.0, 0xa7ff_ffff);
+        assert!(hresult.is_failure());
+        assert!(hresult.is_customer());
+        assert_eq!(hresult.facility(), 0x7ff);
+        assert_eq!(hresult.code(), 0xffff);
+        // Facilities only get 11 bits.
+        assert_eq!(HResult::new(false, 0xffff, 0).facility(), 0x7ff);
+        // E_FAIL
+        assert!(HResult(0x8000_4005).is_failure());
+        assert!(!HResult(0x8000_4005).is_customer());
+    }
+
+    #[test]
+    fn test_sel4() {
+        let hresult = _test_sel4_(Error::NotEnoughMemory);
+        assert!(hresult.is_failure());
+        assert_eq!(hresult.facility(), FACILITY_SEL4);
+        assert_eq!(hresult.code(), Error::NotEnoughMemory as u16);
+        assert_eq!(hresult.error(), Some(JustError::NotEnoughMemory));
+        assert_eq!(hresult.error::<Codec>(), None);
+    }
+
+    #[test]
+    fn test_facility() {
+        let hresult = _decode_(Err(Codec::Unsupported));
+        assert_eq!(hresult.facility(), Codec::FACILITY);
+        assert_eq!(hresult.error(), Some(Codec::Unsupported));
+        assert_eq!(hresult.error::<JustError>(), None);
+        assert_eq!(_test_core_result_(hresult), Err(hresult));
+        assert_eq!(HResult::from(Codec::Corrupt).code(), 1);
+    }
+
+    #[test]
+    fn test_debug() {
+        assert_eq!(
+            std::format!("{:?}", HResult::from(JustError::RangeError)),
+            "HResult(0xa5e40004)"
+        );
+    }
+}
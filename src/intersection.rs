@@ -1,7 +1,5 @@
-use core::convert::Infallible;
+use crate::repr::Repr;
 use core::fmt::Debug;
-use core::num::NonZeroI32;
-use core::ops::{ControlFlow, FromResidual, Try};
 use core::result::Result;
 use strum::{EnumCount, EnumMetadata};
 use enum_extra::NonZeroRepr;
@@ -18,75 +16,24 @@ impl From<Result<(), Error>> for Error {
 
 impl From<Error> for Result<(), Error> {
     fn from(it: Error) -> Self {
-        match NonZeroI32::new(it as i32) {
+        match (it as i32).non_zero() {
             None => Result::Ok(()),
             Some(_) => Result::Err(it),
         }
     }
 }
 
-impl From<Error> for Result<(), JustError> {
-    fn from(it: Error) -> Self {
-        match NonZeroI32::new(it as i32) {
-            // it as i32 == 0.
-            None => Result::Ok(()),
-            Some(error_code) => {
-                let error_code: i32 = i32::from(error_code);
-                // Safety:
-                //
-                // 1. All non-zero error_codes are valid and equivalent JustError codes.
-                // 2. We've determined that the error_code is non-zero.
-                Result::Err(unsafe { core::mem::transmute(error_code) })
-            }
-        }
-    }
-}
-
-impl Try for Error {
-    type Output = ();
-    type Residual = Result<Infallible, JustError>;
-
-    fn branch(self) -> ControlFlow<Self::Residual, Self::Output> {
-        match NonZeroI32::new(self as i32) {
-            // self as i32 == 0.
-            None => ControlFlow::Continue(()),
-            Some(error_code) => {
-                let error_code: i32 = i32::from(error_code);
-                ControlFlow::Break(
-                    // Safety:
-                    //
-                    // 1. All non-zero error_codes are valid and equivalent JustError codes.
-                    // 2. We've determined that the error_code is non-zero.
-                    Err(unsafe { core::mem::transmute(error_code) }),
-                )
-            }
-        }
-    }
-
-    fn from_output((): ()) -> Self {
-        Error::NoError
-    }
-}
-
-impl FromResidual<Result<Infallible, JustError>> for Error {
-    fn from_residual(result: Result<Infallible, JustError>) -> Self {
-        match result {
-            // This should never happen because this is along the Continue branch.
-            // But is required in accordance with the Expected laws.
-            Ok(_) => Error::NoError,
-            Err(just_error) => {
-                let error_code = just_error as i32;
-                // Safety: All JustError error codes are valid and equivalent Error error codes.
-                unsafe { core::mem::transmute(error_code) }
-            }
-        }
-    }
-}
+// The From<Error> for Result<(), JustError>, Try, FromResidual and TryFrom<i32> impls.
+//
+// With tracing, every `?` which returns an Error leaves its location in the trace.
+// `?` on a None is FailedLookup, it is usually a lookup which came up empty.
+crate::impl_intersection!(Error, JustError, i32, none = FailedLookup);
 
 #[derive(Copy, Clone, Debug, EnumCount, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(i32)]
 #[allow(clippy::enum_variant_names)]
-enum Error {
+pub enum Error {
     NoError = 0,
     InvalidArgument,
     InvalidCapability,
@@ -106,8 +53,9 @@ enum Error {
 // In theory I should just write a derive macro to derive this enum from
 // Error without any duplication.  This would not be hard to do, I just have not done it.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, EnumMetadata, NonZeroRepr)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(i32)]
-enum JustError {
+pub enum JustError {
     InvalidArgument = 1,
     InvalidCapability,
     IllegalOperation,
@@ -129,6 +77,12 @@ const _: () = if Error::COUNT == JustError::COUNT + 1 {
     panic!()
 };
 
+// Compile time check that the discriminants are contiguous, TryFrom<i32> relies on it.
+const _: () = if Error::NotEnoughMemory as usize + 1 == Error::COUNT {
+} else {
+    panic!()
+};
+
 #[test]
 fn test_count() {
     assert_eq!(Error::COUNT as usize, Error::NotEnoughMemory as usize + 1)
@@ -183,6 +137,29 @@ fn _test_not_enough_memory_() -> Result<(), JustError> {
     Ok(Error::NotEnoughMemory?)
 }
 
+// Idiomatic Rust helpers, which know nothing of Error.
+struct Exhausted;
+
+impl From<Exhausted> for JustError {
+    fn from(_: Exhausted) -> JustError {
+        JustError::NotEnoughMemory
+    }
+}
+
+fn _allocate_(free: usize) -> Result<usize, Exhausted> {
+    free.checked_sub(1).ok_or(Exhausted)
+}
+
+fn _test_core_result_(free: usize) -> Error {
+    let _ = _allocate_(free)?;
+    Error::NoError
+}
+
+fn _test_option_(slots: &[u8], wanted: u8) -> Error {
+    let _ = slots.iter().position(|slot| *slot == wanted)?;
+    Error::NoError
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -247,4 +224,59 @@ mod test {
     fn test_not_enough_memory() {
         assert_eq!(_test_not_enough_memory_(), Err(JustError::NotEnoughMemory));
     }
+
+    #[test]
+    fn test_question_mark_core_result() {
+        assert_eq!(_test_core_result_(1), Error::NoError);
+        assert_eq!(_test_core_result_(0), Error::NotEnoughMemory);
+    }
+
+    #[test]
+    fn test_question_mark_option() {
+        assert_eq!(_test_option_(&[1, 2], 2), Error::NoError);
+        assert_eq!(_test_option_(&[1, 2], 3), Error::FailedLookup);
+    }
+
+    // The combinators are const where they can be.
+    const _: () = if Error::NoError.is_ok() && Error::RangeError.is_err() {
+    } else {
+        panic!()
+    };
+
+    #[test]
+    fn test_combinators() {
+        assert!(Error::NoError.is_ok());
+        assert!(Error::RangeError.is_err());
+        assert_eq!(Error::NoError.ok(), Some(()));
+        assert_eq!(Error::RangeError.ok(), None);
+        assert_eq!(Error::NoError.err(), None);
+        assert_eq!(Error::RangeError.err(), Some(JustError::RangeError));
+        assert_eq!(
+            Error::NoError.and_then(|| Error::DeleteFirst),
+            Error::DeleteFirst
+        );
+        assert_eq!(
+            Error::RangeError.and_then(|| Error::DeleteFirst),
+            Error::RangeError
+        );
+        assert_eq!(
+            Error::RangeError.or_else(|_| Error::NoError),
+            Error::NoError
+        );
+        assert_eq!(Error::RangeError.map_err(|error| error as i32), Err(4));
+        let mut seen = None;
+        assert_eq!(
+            Error::FailedLookup.inspect_err(|error| seen = Some(*error)),
+            Error::FailedLookup
+        );
+        assert_eq!(seen, Some(JustError::FailedLookup));
+        Error::NoError.unwrap();
+        Error::NoError.expect("no error");
+    }
+
+    #[test]
+    #[should_panic(expected = "while retyping: RangeError")]
+    fn test_expect() {
+        Error::RangeError.expect("while retyping");
+    }
 }
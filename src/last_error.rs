@@ -0,0 +1,177 @@
+use crate::intersection::{Error, JustError};
+
+//
+// errno style, for C callers which ask for the error afterwards rather than look at a return,
+//
+// if (driver_start() != 0) {
+//     int error = sel4_error_get_last_error();
+//     ...
+// }
+//
+// There is one slot per thread, holding the error code of the last `?` to return an
+// intersection::Error on that thread, which is the same place the trace records its frames,
+// or of the last explicit JustError::record(). Error::record() with NoError clears it,
+// as does sel4_error_clear_last_error(). Only intersection::Error's codes go in it,
+// a `?` into any other impl_intersection! type, e.g. an error_domain! of your own,
+// leaves it alone.
+//
+// The C functions are prefixed sel4_error_, they are global symbols, and get_last_error
+// on its own is a name some other C library is bound to export too.
+//
+// From Rust it is last_error::get(), not to be confused with trace::last_error(),
+// which is the whole trace of the last error rather than its code.
+//
+// With std the slot is a thread_local!, without, a #[thread_local] static, which needs
+// the target to support thread locals.
+//
+// Like errno, nothing clears it on success, it is only meaningful after something failed.
+//
+
+#[cfg(any(test, feature = "std"))]
+std::thread_local! {
+    static LAST_ERROR: core::cell::Cell<i32> = core::cell::Cell::new(Error::NoError as i32);
+}
+
+#[cfg(any(test, feature = "std"))]
+pub(crate) fn set(error_code: i32) {
+    LAST_ERROR.with(|last_error| last_error.set(error_code));
+}
+
+#[cfg(any(test, feature = "std"))]
+pub fn get() -> i32 {
+    LAST_ERROR.with(|last_error| last_error.get())
+}
+
+#[cfg(not(any(test, feature = "std")))]
+#[thread_local]
+static mut LAST_ERROR: i32 = Error::NoError as i32;
+
+#[cfg(not(any(test, feature = "std")))]
+pub(crate) fn set(error_code: i32) {
+    // Safety: LAST_ERROR is thread local, and nothing in here can re-enter.
+    unsafe { LAST_ERROR = error_code };
+}
+
+#[cfg(not(any(test, feature = "std")))]
+pub fn get() -> i32 {
+    // Safety: LAST_ERROR is thread local, and nothing in here can re-enter.
+    unsafe { LAST_ERROR }
+}
+
+pub fn clear() {
+    set(Error::NoError as i32);
+}
+
+impl JustError {
+    // Stores it as this thread's last error.
+    pub fn record(self) -> Self {
+        set(self as i32);
+        self
+    }
+}
+
+impl Error {
+    // Stores it as this thread's last error, NoError clears it.
+    pub fn record(self) -> Self {
+        set(self as i32);
+        self
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sel4_error_get_last_error() -> i32 {
+    get()
+}
+
+#[no_mangle]
+pub extern "C" fn sel4_error_clear_last_error() {
+    clear()
+}
+
+fn _test_question_mark_(error: Error) -> Error {
+    error?;
+    Error::NoError
+}
+
+fn _test_other_type_(error: crate::repr::Error8) -> crate::repr::Error8 {
+    error?;
+    crate::repr::Error8::NoError
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+    use std::vec::Vec;
+
+    // What the C side declares.
+    mod c {
+        extern "C" {
+            pub fn sel4_error_get_last_error() -> i32;
+            pub fn sel4_error_clear_last_error();
+        }
+    }
+
+    #[test]
+    fn test_question_mark() {
+        clear();
+        assert_eq!(_test_question_mark_(Error::NoError), Error::NoError);
+        assert_eq!(get(), 0);
+        assert_eq!(_test_question_mark_(Error::DeleteFirst), Error::DeleteFirst);
+        assert_eq!(get(), Error::DeleteFirst as i32);
+        // Success leaves it alone.
+        assert_eq!(_test_question_mark_(Error::NoError), Error::NoError);
+        assert_eq!(get(), Error::DeleteFirst as i32);
+    }
+
+    #[test]
+    fn test_other_type() {
+        Error::DeleteFirst.record();
+        // A u8 enum's codes aren't Error's, they don't go in the slot.
+        assert_eq!(
+            _test_other_type_(crate::repr::Error8::RangeError),
+            crate::repr::Error8::RangeError
+        );
+        assert_eq!(get(), Error::DeleteFirst as i32);
+    }
+
+    #[test]
+    fn test_record() {
+        assert_eq!(JustError::RangeError.record(), JustError::RangeError);
+        assert_eq!(unsafe { c::sel4_error_get_last_error() }, Error::RangeError as i32);
+        Error::NoError.record();
+        assert_eq!(unsafe { c::sel4_error_get_last_error() }, 0);
+        Error::FailedLookup.record();
+        unsafe { c::sel4_error_clear_last_error() };
+        assert_eq!(unsafe { c::sel4_error_get_last_error() }, 0);
+    }
+
+    #[test]
+    fn test_threads() {
+        JustError::IllegalOperation.record();
+        let errors = [
+            JustError::InvalidArgument,
+            JustError::AlignmentError,
+            JustError::TruncatedMessage,
+            JustError::NotEnoughMemory,
+        ];
+        let threads: Vec<_> = errors
+            .iter()
+            .map(|error| {
+                let error = *error;
+                thread::spawn(move || {
+                    assert_eq!(get(), 0, "starts out clear");
+                    for _ in 0..100 {
+                        error.record();
+                        thread::yield_now();
+                        assert_eq!(get(), error as i32);
+                    }
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert_eq!(get(), Error::IllegalOperation as i32);
+    }
+}
@@ -1,6 +1,16 @@
 #![cfg_attr(not(test), no_std)]
 #![allow(non_snake_case, unused_imports, dead_code)]
 #![feature(try_trait_v2, termination_trait_lib)]
+#![cfg_attr(
+    any(test, feature = "trace", feature = "last-error"),
+    feature(thread_local)
+)]
+#![cfg_attr(not(any(test, feature = "std")), feature(core_intrinsics))]
+#![feature(cfg_target_has_atomic)]
+#![cfg_attr(test, feature(once_cell))]
+
+#[cfg(all(feature = "std", not(test)))]
+extern crate std;
 
 // Each of these defines a type Error
 // Their respective implementations are mutually exclusive
@@ -27,4 +37,129 @@ pub mod intersection;
 // I would never want to be subjected to it, nor anyone else for that matter.
 // It is left here for completeness sake, so that others may be saved the expense of repeating it.
 
-// pub mod disjoint;
+pub mod disjoint;
+
+// One of the above as crate::Code, with the crate::Error and crate::Result to go with it,
+// picked by a strategy-* feature.
+pub mod strategy;
+
+pub use strategy::Strategy;
+
+#[cfg(feature = "strategy-union")]
+pub use union::Error as Code;
+
+#[cfg(feature = "strategy-intersection")]
+pub use intersection::Error as Code;
+
+#[cfg(feature = "strategy-disjoint")]
+pub use disjoint::Result as Code;
+
+#[cfg(any(
+    feature = "strategy-union",
+    feature = "strategy-intersection",
+    feature = "strategy-disjoint"
+))]
+pub type Error = <Code as Strategy>::Error;
+
+#[cfg(any(
+    feature = "strategy-union",
+    feature = "strategy-intersection",
+    feature = "strategy-disjoint"
+))]
+pub type Result<T = ()> = core::result::Result<T, Error>;
+
+// Thin wrappers around a few seL4 invocations, returning Result<(), intersection::JustError>.
+//
+// The kernel is behind a trait, so a Linux build can run them against an in-process mock.
+pub mod syscalls;
+
+// Forcing call sites to fail, for testing error paths.
+//
+// fault_point!(site, expr) is just expr, unless the fault-injection feature is enabled.
+#[cfg(any(test, feature = "fault-injection"))]
+pub mod fault;
+
+#[cfg(not(any(test, feature = "fault-injection")))]
+#[macro_export]
+macro_rules! fault_point {
+    ($site:expr, $error:expr) => {
+        $error
+    };
+}
+
+// Where an intersection::Error has been, a per-thread ring buffer of `?` locations.
+#[cfg(any(test, feature = "trace"))]
+pub mod trace;
+
+// sel4_error_get_last_error() for C callers, the code of the last `?` to return an Error on
+// this thread.
+#[cfg(any(test, feature = "last-error"))]
+pub mod last_error;
+
+// .context("while retyping untyped") for Result<_, JustError>, without allocating.
+pub mod context;
+
+// errno, with the same ABI preserving Try as intersection, and the mapping from seL4 errors.
+pub mod errno;
+
+// C structs of an error and a value, ErrorAnd<T>, where `?` gives you the T.
+pub mod error_and;
+
+// C functions which write their result through a pointer, call_with_out(|out| foo(out)).
+pub mod out_param;
+
+// c_error_fns!, Result returning wrappers for an extern block of C functions.
+pub mod extern_fns;
+
+// ffi_boundary, for Rust functions exported to C, no panics across and an int back.
+pub mod ffi;
+
+// AtomicError, the first of the errors from tasks on other threads or cores.
+#[cfg(target_has_atomic = "32")]
+pub mod atomic;
+
+// Error/JustError source from bindgen's constants, for build.rs, so it needs std.
+#[cfg(any(test, feature = "codegen"))]
+pub mod codegen;
+
+// Linux style returns, a non-negative value or -errno packed into an isize.
+pub mod signed;
+
+// HRESULT style codes, severity, facility and code packed into a u32.
+pub mod hresult;
+
+// impl_intersection!/impl_union! for error enums with a repr other than i32, e.g. a u8 status.
+pub mod repr;
+
+// error_domain!, for error enums which embed the codes of the layer below.
+pub mod domain;
+
+// The variant names as &'static str, without the Debug derive.
+pub mod names;
+
+// Display for the error enums, out of one string table, see the strip-* features.
+pub mod strings;
+
+// serde for the error enums, by error code, or by name with serde_impls::by_name.
+#[cfg(feature = "serde")]
+pub mod serde_impls;
+
+// defmt::Format for disjoint::Result, HResult and SignedResult, the enums derive it.
+#[cfg(feature = "defmt")]
+pub mod defmt_impls;
+
+// ufmt::uDebug for the error enums, using the names module.
+#[cfg(feature = "ufmt")]
+pub mod ufmt_impls;
+
+// For error_domain!'s defmt and ufmt impls, in crates which don't depend on them directly.
+#[cfg(feature = "defmt")]
+#[doc(hidden)]
+pub use defmt as __defmt;
+
+#[cfg(feature = "ufmt")]
+#[doc(hidden)]
+pub use ufmt as __ufmt;
+
+// poll_fn and adapters for futures whose output is an intersection::Error.
+pub mod future;
@@ -0,0 +1,257 @@
+use crate::strings::{self, Described};
+use crate::{disjoint, errno, intersection, repr, union};
+
+//
+// Variant names as &'static str, for anything which wants the name without
+// going through the Debug derive, e.g. serde's by name representation.
+//
+// The names are the strings module's, so they are in the image once, and are ""
+// with strip-names, when from_name finds nothing.
+// impl_named! lists the variants for from_name, with an exhaustive match
+// so a variant added to an enum and not here won't compile.
+//
+
+pub trait Named: Described {
+    fn name(self) -> &'static str {
+        strings::name(self.entry())
+    }
+
+    fn from_name(name: &str) -> Option<Self>;
+}
+
+#[macro_export]
+macro_rules! impl_named {
+    ($ty:ty { $($variant:ident),* $(,)? }) => {
+        const _: () = {
+            type It = $ty;
+
+            impl $crate::names::Named for It {
+                fn from_name(name: &str) -> Option<Self> {
+                    [$(It::$variant),*]
+                        .into_iter()
+                        .find(|it| !name.is_empty() && $crate::names::Named::name(*it) == name)
+                }
+            }
+
+            const _: fn(It) = |it| match it {
+                $(It::$variant)|* => (),
+            };
+        };
+    };
+}
+
+impl_named!(union::Error {
+    NoError,
+    InvalidArgument,
+    InvalidCapability,
+    IllegalOperation,
+    RangeError,
+    AlignmentError,
+    FailedLookup,
+    TruncatedMessage,
+    DeleteFirst,
+    RevokeFirst,
+    NotEnoughMemory,
+});
+
+impl_named!(intersection::Error {
+    NoError,
+    InvalidArgument,
+    InvalidCapability,
+    IllegalOperation,
+    RangeError,
+    AlignmentError,
+    FailedLookup,
+    TruncatedMessage,
+    DeleteFirst,
+    RevokeFirst,
+    NotEnoughMemory,
+});
+
+impl_named!(intersection::JustError {
+    InvalidArgument,
+    InvalidCapability,
+    IllegalOperation,
+    RangeError,
+    AlignmentError,
+    FailedLookup,
+    TruncatedMessage,
+    DeleteFirst,
+    RevokeFirst,
+    NotEnoughMemory,
+});
+
+impl_named!(disjoint::NonError {
+    Ok,
+    OkPartial,
+    WarnTruncated,
+    WarnDeprecated,
+});
+
+impl_named!(disjoint::Error {
+    InvalidArgument,
+    InvalidCapability,
+    IllegalOperation,
+    RangeError,
+    AlignmentError,
+    FailedLookup,
+    TruncatedMessage,
+    DeleteFirst,
+    RevokeFirst,
+    NotEnoughMemory,
+    ErrorCodeOutOfRange,
+});
+
+impl_named!(errno::Errno {
+    NoError,
+    EPERM,
+    ENOENT,
+    ESRCH,
+    EINTR,
+    EIO,
+    ENXIO,
+    E2BIG,
+    ENOEXEC,
+    EBADF,
+    ECHILD,
+    EAGAIN,
+    ENOMEM,
+    EACCES,
+    EFAULT,
+    ENOTBLK,
+    EBUSY,
+    EEXIST,
+    EXDEV,
+    ENODEV,
+    ENOTDIR,
+    EISDIR,
+    EINVAL,
+    ENFILE,
+    EMFILE,
+    ENOTTY,
+    ETXTBSY,
+    EFBIG,
+    ENOSPC,
+    ESPIPE,
+    EROFS,
+    EMLINK,
+    EPIPE,
+    EDOM,
+    ERANGE,
+});
+
+impl_named!(errno::JustErrno {
+    EPERM,
+    ENOENT,
+    ESRCH,
+    EINTR,
+    EIO,
+    ENXIO,
+    E2BIG,
+    ENOEXEC,
+    EBADF,
+    ECHILD,
+    EAGAIN,
+    ENOMEM,
+    EACCES,
+    EFAULT,
+    ENOTBLK,
+    EBUSY,
+    EEXIST,
+    EXDEV,
+    ENODEV,
+    ENOTDIR,
+    EISDIR,
+    EINVAL,
+    ENFILE,
+    EMFILE,
+    ENOTTY,
+    ETXTBSY,
+    EFBIG,
+    ENOSPC,
+    ESPIPE,
+    EROFS,
+    EMLINK,
+    EPIPE,
+    EDOM,
+    ERANGE,
+});
+
+impl_named!(repr::Error8 {
+    NoError,
+    InvalidArgument,
+    InvalidCapability,
+    IllegalOperation,
+    RangeError,
+    AlignmentError,
+    FailedLookup,
+    TruncatedMessage,
+    DeleteFirst,
+    RevokeFirst,
+    NotEnoughMemory,
+});
+
+impl_named!(repr::JustError8 {
+    InvalidArgument,
+    InvalidCapability,
+    IllegalOperation,
+    RangeError,
+    AlignmentError,
+    FailedLookup,
+    TruncatedMessage,
+    DeleteFirst,
+    RevokeFirst,
+    NotEnoughMemory,
+});
+
+impl_named!(repr::ErrorWord {
+    NoError,
+    InvalidArgument,
+    InvalidCapability,
+    IllegalOperation,
+    RangeError,
+    AlignmentError,
+    FailedLookup,
+    TruncatedMessage,
+    DeleteFirst,
+    RevokeFirst,
+    NotEnoughMemory,
+});
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use strum::EnumCount;
+
+    #[cfg(not(feature = "strip-names"))]
+    #[test]
+    fn test_names() {
+        assert_eq!(union::Error::NoError.name(), "NoError");
+        assert_eq!(intersection::JustError::RangeError.name(), "RangeError");
+        assert_eq!(disjoint::NonError::OkPartial.name(), "OkPartial");
+        assert_eq!(
+            disjoint::Error::from_name("ErrorCodeOutOfRange").map(|error| error as i32),
+            Some(disjoint::Error::ErrorCodeOutOfRange as i32)
+        );
+        assert_eq!(intersection::JustError::from_name("NoError"), None);
+    }
+
+    #[cfg(not(feature = "strip-names"))]
+    #[test]
+    fn test_round_trip() {
+        for error_code in 0..intersection::Error::COUNT as i32 {
+            let error = intersection::Error::try_from(error_code).unwrap();
+            assert_eq!(intersection::Error::from_name(error.name()), Some(error));
+            // The names should match the Debug derive.
+            assert_eq!(std::format!("{:?}", error), error.name());
+        }
+    }
+
+    #[cfg(feature = "strip-names")]
+    #[test]
+    fn test_stripped() {
+        assert_eq!(union::Error::NoError.name(), "");
+        assert_eq!(union::Error::from_name(""), None);
+        assert_eq!(union::Error::from_name("NoError"), None);
+    }
+}
@@ -0,0 +1,197 @@
+use crate::intersection::{Error, JustError};
+use crate::strategy::Strategy;
+use core::mem::MaybeUninit;
+use core::result::Result as CoreResult;
+
+//
+// C functions which return the error, and the value through a pointer,
+//
+// seL4_Error foo(seL4_Word arg, seL4_Word *out);
+//
+// call_with_out gives the closure a pointer to uninitialized memory, and reads it back
+// only if the call returned NoError:
+//
+// let word = unsafe { call_with_out(|out| foo(arg, out)) }?;
+//
+// For more than one, call_with_outs gives a tuple of pointers and returns a tuple of values,
+// unsafe { call_with_outs(|(a, b)| bar(a, b)) } -> Result<(A, B), JustError>.
+//
+// The function can return any of the strategies, the error is then the Strategy's Error,
+// e.g. a union::Error for a function returning union::Error.
+//
+// On an error nothing is read, and nothing is dropped, so a value the function did write
+// before failing is leaked rather than dropped, which is what C would expect anyway.
+//
+// scripts/miri.sh runs the tests under Miri, which catches reading the uninitialized
+// out on the error path.
+//
+
+// One *mut T worth of uninitialized memory.
+pub struct OutParam<T>(MaybeUninit<T>);
+
+impl<T> OutParam<T> {
+    pub const fn uninit() -> Self {
+        OutParam(MaybeUninit::uninit())
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.0.as_mut_ptr()
+    }
+
+    /// # Safety
+    ///
+    /// Something has written a valid T through as_mut_ptr().
+    pub unsafe fn assume_init(self) -> T {
+        self.0.assume_init()
+    }
+}
+
+impl<T> Default for OutParam<T> {
+    fn default() -> Self {
+        OutParam::uninit()
+    }
+}
+
+/// # Safety
+///
+/// When `f` returns success, it must have written a valid T through the pointer.
+/// It must not keep the pointer past returning.
+pub unsafe fn call_with_out<T, S, F>(f: F) -> CoreResult<T, S::Error>
+where
+    S: Strategy,
+    F: FnOnce(*mut T) -> S,
+{
+    let mut out = OutParam::uninit();
+    f(out.as_mut_ptr()).into_result()?;
+    // Safety: f returned success, so it wrote the T.
+    Ok(out.assume_init())
+}
+
+// Tuples of out params, (A, B) is given as (*mut A, *mut B).
+pub trait OutParams: Sized {
+    type Uninit: Default;
+    type Pointers;
+
+    fn as_mut_ptrs(uninit: &mut Self::Uninit) -> Self::Pointers;
+
+    /// # Safety
+    ///
+    /// Something has written a valid value through every one of the pointers.
+    unsafe fn assume_init(uninit: Self::Uninit) -> Self;
+}
+
+macro_rules! impl_out_params {
+    ($(($($out:ident),+)),* $(,)?) => {
+        $(
+            #[allow(non_snake_case)]
+            impl<$($out),+> OutParams for ($($out,)+) {
+                type Uninit = ($(OutParam<$out>,)+);
+                type Pointers = ($(*mut $out,)+);
+
+                fn as_mut_ptrs(uninit: &mut Self::Uninit) -> Self::Pointers {
+                    let ($($out,)+) = uninit;
+                    ($($out.as_mut_ptr(),)+)
+                }
+
+                unsafe fn assume_init(uninit: Self::Uninit) -> Self {
+                    let ($($out,)+) = uninit;
+                    ($($out.assume_init(),)+)
+                }
+            }
+        )*
+    };
+}
+
+impl_out_params!((A), (A, B), (A, B, C), (A, B, C, D));
+
+/// # Safety
+///
+/// When `f` returns success, it must have written a valid value through every one of the pointers.
+/// It must not keep the pointers past returning.
+pub unsafe fn call_with_outs<O, S, F>(f: F) -> CoreResult<O, S::Error>
+where
+    O: OutParams,
+    S: Strategy,
+    F: FnOnce(O::Pointers) -> S,
+{
+    let mut uninit = O::Uninit::default();
+    f(O::as_mut_ptrs(&mut uninit)).into_result()?;
+    // Safety: f returned success, so it wrote all of them.
+    Ok(O::assume_init(uninit))
+}
+
+// Stands in for the C side, writes *paddr only on success.
+unsafe fn _get_address_(vaddr: usize, paddr: *mut usize) -> Error {
+    if vaddr % 0x1000 != 0 {
+        return Error::AlignmentError;
+    }
+    paddr.write(vaddr + 0x8000);
+    Error::NoError
+}
+
+// Writes the first before failing on the second, like a partially completed C call.
+unsafe fn _split_(word: u64, high: *mut u16, low: *mut u16) -> Error {
+    high.write((word >> 16) as u16);
+    if word > u32::MAX as u64 {
+        return Error::RangeError;
+    }
+    low.write(word as u16);
+    Error::NoError
+}
+
+fn _test_out_(vaddr: usize) -> CoreResult<usize, JustError> {
+    // Safety: _get_address_ writes paddr whenever it returns NoError.
+    unsafe { call_with_out(|paddr| _get_address_(vaddr, paddr)) }
+}
+
+fn _test_outs_(word: u64) -> CoreResult<(u16, u16), JustError> {
+    // Safety: _split_ writes both whenever it returns NoError.
+    unsafe { call_with_outs(|(high, low)| _split_(word, high, low)) }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::string::String;
+
+    #[test]
+    fn test_out() {
+        assert_eq!(_test_out_(0x2000), Ok(0xa000));
+        // paddr is still uninitialized, under miri reading it would be an error.
+        assert_eq!(_test_out_(0x2001), Err(JustError::AlignmentError));
+    }
+
+    #[test]
+    fn test_outs() {
+        assert_eq!(_test_outs_(0x1234_5678), Ok((0x1234, 0x5678)));
+        assert_eq!(
+            _test_outs_(u64::MAX),
+            Err(JustError::RangeError),
+            "low isn't written"
+        );
+    }
+
+    // Something with a destructor, so miri checks it is neither leaked nor dropped twice.
+    #[test]
+    fn test_drop() {
+        let result = unsafe {
+            call_with_outs::<(String, u8), _, _>(|(name, count)| {
+                name.write(String::from("untyped"));
+                count.write(1);
+                Error::NoError
+            })
+        };
+        assert_eq!(result, Ok((String::from("untyped"), 1)));
+        let result = unsafe { call_with_out::<String, _, _>(|_| Error::FailedLookup) };
+        assert_eq!(result, Err(JustError::FailedLookup));
+    }
+
+    #[test]
+    fn test_out_param() {
+        let mut out = OutParam::<u32>::default();
+        unsafe {
+            out.as_mut_ptr().write(7);
+            assert_eq!(out.assume_init(), 7);
+        }
+    }
+}
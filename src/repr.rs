@@ -0,0 +1,525 @@
+use core::num::{
+    NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU16, NonZeroU32,
+    NonZeroU64, NonZeroU8, NonZeroUsize,
+};
+use enum_extra::NonZeroRepr;
+use strum::EnumMetadata;
+
+pub use strum::EnumCount;
+
+//
+// The union and intersection strategies, for whatever width the C side uses.
+//
+// seL4_Error is an int, but firmware status codes are often a uint8_t
+// and some interfaces return errors as a seL4_Word. The enums just need the matching
+// #[repr(..)], and impl_intersection!/impl_union! give them the same impls
+// intersection::Error and union::Error have, with Repr picking the NonZero type.
+//
+// Both macros expect the enums to be in scope by name, the no error variant to be
+// NoError = 0, the discriminants to be contiguous, and for intersection,
+// the JustError enum to be the same discriminants minus NoError.
+// Those last two are on you, see the const checks in intersection.rs for how to check them.
+//
+// Because JustError has no 0, Option<JustError> is the same size as the repr.
+//
+// `?` works on a Result whose error converts into the (Just)Error, and with a trailing
+// `none = Variant`, e.g. impl_union!(Error, i32, none = FailedLookup), on an Option,
+// a None returning that variant.
+//
+// disjoint is left as i32. Its non errors count down from 0, so it only makes sense for
+// a signed repr, not the uint8_t statuses and seL4_Word errors this is for, and its own
+// comment has it as the one to dustbin.
+//
+
+pub trait Repr: Copy + Eq {
+    type NonZero: Copy + Eq;
+
+    fn non_zero(self) -> Option<Self::NonZero>;
+    fn get(non_zero: Self::NonZero) -> Self;
+}
+
+macro_rules! impl_repr {
+    ($($repr:ty => $non_zero:ty),* $(,)?) => {
+        $(
+            impl Repr for $repr {
+                type NonZero = $non_zero;
+
+                fn non_zero(self) -> Option<$non_zero> {
+                    <$non_zero>::new(self)
+                }
+
+                fn get(non_zero: $non_zero) -> Self {
+                    non_zero.get()
+                }
+            }
+        )*
+    };
+}
+
+impl_repr! {
+    u8 => NonZeroU8,
+    u16 => NonZeroU16,
+    u32 => NonZeroU32,
+    u64 => NonZeroU64,
+    usize => NonZeroUsize,
+    i8 => NonZeroI8,
+    i16 => NonZeroI16,
+    i32 => NonZeroI32,
+    i64 => NonZeroI64,
+    isize => NonZeroIsize,
+}
+
+// With tracing, every `?` through an impl_intersection! FromResidual leaves its location in the trace,
+// and with last-error, a `?` into an intersection::Error leaves its error code in the last_error slot.
+// That slot is intersection::Error's, the other impl_intersection! types' codes would collide with
+// its codes there, or not fit in it.
+//
+// The trace's codes are i32's, a code of a wider repr which doesn't fit in one isn't recorded,
+// rather than recorded as some other code.
+#[doc(hidden)]
+#[inline(always)]
+#[track_caller]
+#[cfg_attr(
+    not(any(test, feature = "trace", feature = "last-error")),
+    allow(unused_variables)
+)]
+pub fn __record<E: 'static, R: TryInto<i32>>(error_code: R) {
+    #[cfg(any(test, feature = "trace", feature = "last-error"))]
+    if let Ok(error_code) = error_code.try_into() {
+        #[cfg(any(test, feature = "trace"))]
+        crate::trace::record(error_code, core::panic::Location::caller());
+        #[cfg(any(test, feature = "last-error"))]
+        if core::any::TypeId::of::<E>() == core::any::TypeId::of::<crate::intersection::Error>() {
+            crate::last_error::set(error_code);
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! impl_intersection {
+    ($error:ident, $just_error:ident, $repr:ty $(, none = $none:ident)?) => {
+        $crate::impl_intersection!(@try $error, $just_error, $repr $(, none = $none)?);
+        $crate::impl_try_from_repr!($error, $repr);
+    };
+    // Everything but TryFrom, which needs contiguous discriminants.
+    (@try $error:ident, $just_error:ident, $repr:ty $(, none = $none:ident)?) => {
+        $crate::impl_combinators!($error, $just_error, $repr);
+
+        impl ::core::convert::From<$error> for ::core::result::Result<(), $just_error> {
+            fn from(it: $error) -> Self {
+                match <$repr as $crate::repr::Repr>::non_zero(it as $repr) {
+                    // it as $repr == 0.
+                    None => Ok(()),
+                    Some(error_code) => {
+                        let error_code: $repr = <$repr as $crate::repr::Repr>::get(error_code);
+                        // Safety:
+                        //
+                        // 1. All non-zero error_codes are valid and equivalent JustError codes.
+                        // 2. We've determined that the error_code is non-zero.
+                        Err(unsafe { ::core::mem::transmute::<$repr, $just_error>(error_code) })
+                    }
+                }
+            }
+        }
+
+        impl ::core::ops::Try for $error {
+            type Output = ();
+            type Residual = ::core::result::Result<::core::convert::Infallible, $just_error>;
+
+            fn branch(self) -> ::core::ops::ControlFlow<Self::Residual, Self::Output> {
+                match ::core::result::Result::<(), $just_error>::from(self) {
+                    Ok(()) => ::core::ops::ControlFlow::Continue(()),
+                    Err(just_error) => ::core::ops::ControlFlow::Break(Err(just_error)),
+                }
+            }
+
+            fn from_output((): ()) -> Self {
+                $error::NoError
+            }
+        }
+
+        // Anything which converts into a JustError, so `?` works on idiomatic Rust helpers too.
+        impl<E: ::core::convert::Into<$just_error>>
+            ::core::ops::FromResidual<::core::result::Result<::core::convert::Infallible, E>>
+            for $error
+        {
+            #[track_caller]
+            fn from_residual(result: ::core::result::Result<::core::convert::Infallible, E>) -> Self {
+                match result {
+                    // This should never happen because this is along the Continue branch.
+                    // But is required in accordance with the Expected laws.
+                    Ok(_) => $error::NoError,
+                    Err(error) => {
+                        let just_error: $just_error = error.into();
+                        let error_code = just_error as $repr;
+                        $crate::repr::__record::<$error, $repr>(error_code);
+                        // Safety: All JustError error codes are valid and equivalent Error error codes.
+                        unsafe { ::core::mem::transmute::<$repr, $error>(error_code) }
+                    }
+                }
+            }
+        }
+
+        $(
+            // `?` on a None returns $none.
+            impl ::core::ops::FromResidual<::core::option::Option<::core::convert::Infallible>> for $error {
+                #[track_caller]
+                fn from_residual(_: ::core::option::Option<::core::convert::Infallible>) -> Self {
+                    <$error as ::core::ops::FromResidual<_>>::from_residual(
+                        Err::<::core::convert::Infallible, _>($just_error::$none),
+                    )
+                }
+            }
+        )?
+    };
+}
+
+#[macro_export]
+macro_rules! impl_union {
+    ($error:ident, $repr:ty $(, none = $none:ident)?) => {
+        impl ::core::convert::From<$error> for ::core::result::Result<(), $error> {
+            fn from(it: $error) -> Self {
+                match <$repr as $crate::repr::Repr>::non_zero(it as $repr) {
+                    None => Ok(()),
+                    Some(_) => Err(it),
+                }
+            }
+        }
+
+        impl ::core::ops::Try for $error {
+            type Output = ();
+            type Residual = $error;
+
+            fn branch(self) -> ::core::ops::ControlFlow<Self::Residual, ()> {
+                match <$repr as $crate::repr::Repr>::non_zero(self as $repr) {
+                    None => ::core::ops::ControlFlow::Continue(()),
+                    Some(_) => ::core::ops::ControlFlow::Break(self),
+                }
+            }
+
+            fn from_output((): ()) -> Self {
+                $error::NoError
+            }
+        }
+
+        impl ::core::ops::FromResidual<$error> for $error {
+            fn from_residual(e: $error) -> Self {
+                e
+            }
+        }
+
+        // Anything which converts into an Error, so `?` works on idiomatic Rust helpers too.
+        impl<E: ::core::convert::Into<$error>>
+            ::core::ops::FromResidual<::core::result::Result<::core::convert::Infallible, E>>
+            for $error
+        {
+            fn from_residual(result: ::core::result::Result<::core::convert::Infallible, E>) -> Self {
+                match result {
+                    // This should never happen because this is along the Continue branch.
+                    Ok(_) => $error::NoError,
+                    Err(error) => error.into(),
+                }
+            }
+        }
+
+        $(
+            // `?` on a None returns $none.
+            impl ::core::ops::FromResidual<::core::option::Option<::core::convert::Infallible>> for $error {
+                fn from_residual(_: ::core::option::Option<::core::convert::Infallible>) -> Self {
+                    $error::$none
+                }
+            }
+        )?
+
+        $crate::impl_combinators!($error, $error, $repr);
+        $crate::impl_try_from_repr!($error, $repr);
+    };
+}
+
+// Result's combinators directly on the Error, without going through Result<(), JustError> first.
+// For union, $just_error is just $error.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! impl_combinators {
+    ($error:ident, $just_error:ident, $repr:ty) => {
+        impl $error {
+            pub const fn is_ok(self) -> bool {
+                self as $repr == 0
+            }
+
+            pub const fn is_err(self) -> bool {
+                !self.is_ok()
+            }
+
+            pub const fn ok(self) -> ::core::option::Option<()> {
+                if self.is_ok() {
+                    Some(())
+                } else {
+                    None
+                }
+            }
+
+            pub const fn err(self) -> ::core::option::Option<$just_error> {
+                if self.is_ok() {
+                    None
+                } else {
+                    // Safety: All non-zero error codes are valid and equivalent JustError codes.
+                    Some(unsafe { ::core::mem::transmute::<$repr, $just_error>(self as $repr) })
+                }
+            }
+
+            pub fn and_then<F: ::core::ops::FnOnce() -> Self>(self, f: F) -> Self {
+                if self.is_ok() {
+                    f()
+                } else {
+                    self
+                }
+            }
+
+            pub fn or_else<F: ::core::ops::FnOnce($just_error) -> Self>(self, f: F) -> Self {
+                match self.err() {
+                    None => self,
+                    Some(error) => f(error),
+                }
+            }
+
+            pub fn map_err<E, F: ::core::ops::FnOnce($just_error) -> E>(
+                self,
+                f: F,
+            ) -> ::core::result::Result<(), E> {
+                match self.err() {
+                    None => Ok(()),
+                    Some(error) => Err(f(error)),
+                }
+            }
+
+            pub fn inspect_err<F: ::core::ops::FnOnce(&$just_error)>(self, f: F) -> Self {
+                if let Some(error) = self.err() {
+                    f(&error);
+                }
+                self
+            }
+
+            #[track_caller]
+            pub fn unwrap(self) {
+                if let Some(error) = self.err() {
+                    panic!("called `{}::unwrap()` on {:?}", stringify!($error), error);
+                }
+            }
+
+            #[track_caller]
+            pub fn expect(self, msg: &str) {
+                if let Some(error) = self.err() {
+                    panic!("{}: {:?}", msg, error);
+                }
+            }
+        }
+    };
+}
+
+// The checked way in, for codes which didn't come from an Error to begin with.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! impl_try_from_repr {
+    ($error:ident, $repr:ty) => {
+        impl ::core::convert::TryFrom<$repr> for $error {
+            type Error = $repr;
+
+            fn try_from(error_code: $repr) -> ::core::result::Result<Self, $repr> {
+                // Compared as u128 so a u64/i64 code isn't truncated on a 32 bit target,
+                // and negative codes sign extend to something larger than COUNT.
+                if (error_code as u128) < <$error as $crate::repr::EnumCount>::COUNT as u128 {
+                    // Safety: The discriminants of Error are contiguous from 0 to Error::COUNT - 1.
+                    Ok(unsafe { ::core::mem::transmute::<$repr, $error>(error_code) })
+                } else {
+                    Err(error_code)
+                }
+            }
+        }
+    };
+}
+
+// seL4's errors as a firmware uint8_t status.
+#[derive(Copy, Clone, Debug, EnumCount, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+#[allow(clippy::enum_variant_names)]
+pub enum Error8 {
+    NoError = 0,
+    InvalidArgument,
+    InvalidCapability,
+    IllegalOperation,
+    RangeError,
+    AlignmentError,
+    FailedLookup,
+    TruncatedMessage,
+    DeleteFirst,
+    RevokeFirst,
+    NotEnoughMemory,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, EnumMetadata, NonZeroRepr)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum JustError8 {
+    InvalidArgument = 1,
+    InvalidCapability,
+    IllegalOperation,
+    RangeError,
+    AlignmentError,
+    FailedLookup,
+    TruncatedMessage,
+    DeleteFirst,
+    RevokeFirst,
+    NotEnoughMemory,
+}
+
+crate::impl_intersection!(Error8, JustError8, u8);
+
+// seL4's errors as a seL4_Word.
+#[derive(Copy, Clone, Debug, EnumCount, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(usize)]
+#[allow(clippy::enum_variant_names)]
+pub enum ErrorWord {
+    NoError = 0,
+    InvalidArgument,
+    InvalidCapability,
+    IllegalOperation,
+    RangeError,
+    AlignmentError,
+    FailedLookup,
+    TruncatedMessage,
+    DeleteFirst,
+    RevokeFirst,
+    NotEnoughMemory,
+}
+
+crate::impl_union!(ErrorWord, usize);
+
+// Compile time check that JustError8 is one less than Error8.
+const _: () = if Error8::COUNT == JustError8::COUNT + 1 {
+} else {
+    panic!()
+};
+
+// Compile time check that the discriminants are contiguous, TryFrom relies on it.
+const _: () = if Error8::NotEnoughMemory as usize + 1 == Error8::COUNT
+    && ErrorWord::NotEnoughMemory as usize + 1 == ErrorWord::COUNT
+{
+} else {
+    panic!()
+};
+
+// Compile time check that the niche is still there.
+const _: () = if core::mem::size_of::<Option<JustError8>>() == 1
+    && core::mem::size_of::<Error8>() == 1
+    && core::mem::size_of::<ErrorWord>() == core::mem::size_of::<usize>()
+{
+} else {
+    panic!()
+};
+
+fn _test_ok_8_() -> Error8 {
+    let () = Error8::NoError?;
+    Error8::NoError
+}
+
+fn _test_range_error_8_() -> Result<(), JustError8> {
+    Ok(Error8::RangeError?)
+}
+
+fn _test_forward_8_(error: Error8) -> Error8 {
+    error?;
+    Error8::NoError
+}
+
+fn _test_word_(error: ErrorWord) -> ErrorWord {
+    error?;
+    ErrorWord::NoError
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ok() {
+        assert_eq!(_test_ok_8_(), Error8::NoError);
+        assert_eq!(_test_forward_8_(Error8::NoError), Error8::NoError);
+        assert_eq!(_test_word_(ErrorWord::NoError), ErrorWord::NoError);
+    }
+
+    #[test]
+    fn test_u8() {
+        assert_eq!(_test_range_error_8_(), Err(JustError8::RangeError));
+        assert_eq!(
+            _test_forward_8_(Error8::NotEnoughMemory),
+            Error8::NotEnoughMemory
+        );
+        assert_eq!(core::mem::size_of::<Option<JustError8>>(), 1);
+    }
+
+    #[test]
+    fn test_word() {
+        assert_eq!(
+            _test_word_(ErrorWord::FailedLookup),
+            ErrorWord::FailedLookup
+        );
+    }
+
+    #[test]
+    fn test_try_from() {
+        assert_eq!(Error8::try_from(4), Ok(Error8::RangeError));
+        assert_eq!(
+            Error8::try_from(Error8::COUNT as u8),
+            Err(Error8::COUNT as u8)
+        );
+        assert_eq!(Error8::try_from(u8::MAX), Err(u8::MAX));
+        assert_eq!(ErrorWord::try_from(10), Ok(ErrorWord::NotEnoughMemory));
+        assert_eq!(ErrorWord::try_from(usize::MAX), Err(usize::MAX));
+        // Negative codes are out of range, not wrapped around into it.
+        assert_eq!(crate::intersection::Error::try_from(-1), Err(-1));
+        assert_eq!(crate::union::Error::try_from(i32::MIN), Err(i32::MIN));
+    }
+
+    // Two codes which would be in range if they were truncated to 32 bits.
+    #[derive(Copy, Clone, Debug, EnumCount, Eq, PartialEq)]
+    #[repr(i64)]
+    enum Status64 {
+        Ready = 0,
+        Busy,
+    }
+
+    crate::impl_try_from_repr!(Status64, i64);
+
+    // seL4_Word sized, with a code which doesn't fit in the trace's i32.
+    crate::error_domain! {
+        enum WideError, JustWideError: u64 {
+            Small = 1,
+            Huge = 0x1_0000_0001,
+        }
+    }
+
+    fn _test_wide_(error: WideError) -> WideError {
+        error?;
+        WideError::NoError
+    }
+
+    #[test]
+    fn test_record_wide() {
+        crate::trace::clear();
+        assert_eq!(_test_wide_(WideError::Huge), WideError::Huge);
+        assert!(crate::trace::last_error().is_empty(), "not recorded as 1");
+        assert_eq!(_test_wide_(WideError::Small), WideError::Small);
+        assert_eq!(crate::trace::last_error().error_code(), Some(1));
+    }
+
+    #[test]
+    fn test_try_from_wide() {
+        assert_eq!(Status64::try_from(1), Ok(Status64::Busy));
+        assert_eq!(Status64::try_from(0x1_0000_0001), Err(0x1_0000_0001));
+        assert_eq!(Status64::try_from(-0x1_0000_0000), Err(-0x1_0000_0000));
+        assert_eq!(Status64::try_from(i64::MIN), Err(i64::MIN));
+    }
+}
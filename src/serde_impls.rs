@@ -0,0 +1,163 @@
+use crate::names::Named;
+use crate::{disjoint, intersection, union};
+use core::fmt;
+use core::marker::PhantomData;
+use core::result::Result as CoreResult;
+use serde::de::{self, Deserializer, Unexpected, Visitor};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+//
+// serde for the error enums.
+//
+// By default they are the error code, an i32 just like the C side has it, so
+// postcard (or anything else) gets the same value that would have crossed the FFI.
+// Deserializing goes through TryFrom<i32>, so an unknown code is an error rather than UB.
+//
+// For something a person reads, e.g. JSON traces, use the name instead:
+//
+// #[derive(Serialize, Deserialize)]
+// struct Frame {
+//     #[serde(with = "error_compat_rust_experiments::serde_impls::by_name")]
+//     error: intersection::Error,
+// }
+//
+// The names are the names module's, so with strip-names there are none to go by.
+//
+
+macro_rules! impl_serde_by_code {
+    ($ty:ty, $expecting:literal, $try_from:expr) => {
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> CoreResult<S::Ok, S::Error> {
+                serializer.serialize_i32(*self as i32)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> CoreResult<Self, D::Error> {
+                let error_code = i32::deserialize(deserializer)?;
+                let try_from: fn(i32) -> Option<$ty> = $try_from;
+                try_from(error_code).ok_or_else(|| {
+                    de::Error::invalid_value(Unexpected::Signed(error_code as i64), &$expecting)
+                })
+            }
+        }
+    };
+}
+
+impl_serde_by_code!(union::Error, "an seL4 error code", |error_code| {
+    union::Error::try_from(error_code).ok()
+});
+
+impl_serde_by_code!(intersection::Error, "an seL4 error code", |error_code| {
+    intersection::Error::try_from(error_code).ok()
+});
+
+impl_serde_by_code!(
+    intersection::JustError,
+    "a non-zero seL4 error code",
+    |error_code| {
+        let error = intersection::Error::try_from(error_code).ok()?;
+        CoreResult::<(), intersection::JustError>::from(error).err()
+    }
+);
+
+impl_serde_by_code!(disjoint::Error, "a disjoint error code", |error_code| {
+    disjoint::Error::try_from(error_code).ok()
+});
+
+pub mod by_name {
+    use super::*;
+
+    pub fn serialize<T: Named, S: Serializer>(
+        it: &T,
+        serializer: S,
+    ) -> CoreResult<S::Ok, S::Error> {
+        serializer.serialize_str(it.name())
+    }
+
+    pub fn deserialize<'de, T: Named, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> CoreResult<T, D::Error> {
+        deserializer.deserialize_str(NameVisitor(PhantomData))
+    }
+
+    struct NameVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Named> Visitor<'de> for NameVisitor<T> {
+        type Value = T;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "an error name")
+        }
+
+        fn visit_str<E: de::Error>(self, name: &str) -> CoreResult<T, E> {
+            T::from_name(name).ok_or_else(|| E::invalid_value(Unexpected::Str(name), &self))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intersection::{Error, JustError};
+
+    #[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
+    struct Frame {
+        #[serde(with = "by_name")]
+        error: Error,
+        line: u32,
+    }
+
+    #[test]
+    fn test_by_code() {
+        assert_eq!(serde_json::to_string(&Error::RangeError).unwrap(), "4");
+        assert_eq!(
+            serde_json::from_str::<union::Error>("10").unwrap(),
+            union::Error::NotEnoughMemory
+        );
+        assert_eq!(
+            serde_json::from_str::<JustError>("1").unwrap(),
+            JustError::InvalidArgument
+        );
+        assert_eq!(
+            serde_json::from_str::<disjoint::Error>("11").unwrap() as i32,
+            disjoint::Error::ErrorCodeOutOfRange as i32
+        );
+    }
+
+    #[test]
+    fn test_unknown_code() {
+        assert!(serde_json::from_str::<Error>("11").is_err());
+        assert!(serde_json::from_str::<Error>("-1").is_err());
+        assert!(serde_json::from_str::<JustError>("0").is_err());
+        assert!(serde_json::from_str::<disjoint::Error>("0").is_err());
+    }
+
+    #[cfg(not(feature = "strip-names"))]
+    #[test]
+    fn test_by_name() {
+        let frame = Frame {
+            error: Error::DeleteFirst,
+            line: 42,
+        };
+        let json = serde_json::to_string(&frame).unwrap();
+        assert_eq!(json, r#"{"error":"DeleteFirst","line":42}"#);
+        assert_eq!(serde_json::from_str::<Frame>(&json).unwrap(), frame);
+        assert!(serde_json::from_str::<Frame>(r#"{"error":"Nope","line":1}"#).is_err());
+    }
+
+    #[test]
+    fn test_postcard() {
+        let mut buffer = [0u8; 8];
+        let bytes = postcard::to_slice(&Error::RevokeFirst, &mut buffer).unwrap();
+        assert_eq!(
+            postcard::from_bytes::<Error>(bytes).unwrap(),
+            Error::RevokeFirst
+        );
+        // The same bytes as the i32 error code.
+        let mut expected = [0u8; 8];
+        let code = postcard::to_slice(&(Error::RevokeFirst as i32), &mut expected).unwrap();
+        assert_eq!(bytes, code);
+    }
+}
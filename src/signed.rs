@@ -0,0 +1,322 @@
+use crate::errno::{Errno, JustErrno};
+use crate::intersection::JustError;
+use core::convert::Infallible;
+use core::fmt::{self, Debug};
+use core::marker::PhantomData;
+use core::ops::{ControlFlow, FromResidual, Try};
+use core::result::Result as CoreResult;
+
+//
+// The linux syscall convention, a non-negative value on success and -errno on failure,
+// all in the one isize. Like disjoint::Result the success and error share the one
+// ABI type, but here it is just the isize so there is no union and nothing unsafe.
+//
+// As linux does, only -4095..=-1 are errors, anything further below that is a success
+// value which happens to have the top bit set (e.g. an address returned by mmap).
+//
+// Errno only covers errno-base, so `?` carries the errno as it was in an ErrnoCode,
+// and ErrnoCode::errno() is the JustErrno, or None for an errno beyond errno-base.
+// A SignedResult which is `?`ed into another one keeps its errno, whatever it was.
+//
+// That makes the Residual ErrnoCode, not Errno. A Residual of Errno would have had to turn
+// every errno beyond errno-base into one it has, EIO, on its way through `?`.
+//
+// from_output panics on a success value in the errno range, -4095..=-1 as an isize,
+// which would otherwise come out the other side as an error.
+//
+
+pub const MAX_ERRNO: isize = 4095;
+
+// What the success value is, usize unless you need something more specific.
+pub trait Output: Sized {
+    fn from_raw(raw: usize) -> Self;
+    fn into_raw(self) -> usize;
+}
+
+impl Output for usize {
+    fn from_raw(raw: usize) -> Self {
+        raw
+    }
+
+    fn into_raw(self) -> usize {
+        self
+    }
+}
+
+// A failed SignedResult's errno, 1..=MAX_ERRNO.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ErrnoCode(i32);
+
+impl ErrnoCode {
+    pub const fn code(self) -> i32 {
+        self.0
+    }
+
+    // None for an errno beyond errno-base.
+    pub fn errno(self) -> Option<JustErrno> {
+        Errno::try_from(self.0)
+            .ok()
+            .and_then(|errno| CoreResult::<(), JustErrno>::from(errno).err())
+    }
+}
+
+impl From<JustErrno> for ErrnoCode {
+    fn from(errno: JustErrno) -> Self {
+        ErrnoCode(errno as i32)
+    }
+}
+
+#[repr(transparent)]
+pub struct SignedResult<T = usize> {
+    raw: isize,
+    output: PhantomData<T>,
+}
+
+impl<T> SignedResult<T> {
+    pub const fn from_raw(raw: isize) -> Self {
+        SignedResult {
+            raw,
+            output: PhantomData,
+        }
+    }
+
+    pub const fn raw(self) -> isize {
+        self.raw
+    }
+
+    pub const fn is_err(self) -> bool {
+        -MAX_ERRNO <= self.raw && self.raw < 0
+    }
+
+    pub const fn errno_code(self) -> Option<i32> {
+        if self.is_err() {
+            Some(-self.raw as i32)
+        } else {
+            None
+        }
+    }
+
+    pub const fn errno(self) -> Option<ErrnoCode> {
+        match self.errno_code() {
+            Some(error_code) => Some(ErrnoCode(error_code)),
+            None => None,
+        }
+    }
+}
+
+impl<T> Clone for SignedResult<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for SignedResult<T> {}
+
+impl<T> PartialEq for SignedResult<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl<T> Eq for SignedResult<T> {}
+
+impl<T> Debug for SignedResult<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.errno_code() {
+            Some(error_code) => write!(f, "errno: {}", error_code),
+            None => write!(f, "ok: {}", self.raw as usize),
+        }
+    }
+}
+
+impl<T: Output> Try for SignedResult<T> {
+    type Output = T;
+    type Residual = ErrnoCode;
+
+    fn branch(self) -> ControlFlow<Self::Residual, Self::Output> {
+        match self.errno() {
+            None => ControlFlow::Continue(T::from_raw(self.raw as usize)),
+            Some(errno) => ControlFlow::Break(errno),
+        }
+    }
+
+    fn from_output(output: T) -> Self {
+        let it = SignedResult::from_raw(output.into_raw() as isize);
+        assert!(!it.is_err(), "success value in the errno range");
+        it
+    }
+}
+
+impl<T> FromResidual<ErrnoCode> for SignedResult<T> {
+    fn from_residual(errno: ErrnoCode) -> Self {
+        SignedResult::from_raw(-(errno.code() as isize))
+    }
+}
+
+// `?` on an Errno, in a function returning SignedResult.
+impl<T> FromResidual<CoreResult<Infallible, JustErrno>> for SignedResult<T> {
+    fn from_residual(result: CoreResult<Infallible, JustErrno>) -> Self {
+        match result {
+            Ok(infallible) => match infallible {},
+            Err(errno) => SignedResult::from_residual(ErrnoCode::from(errno)),
+        }
+    }
+}
+
+// `?` on an seL4 error, when emulating a linux syscall on top of seL4.
+impl<T> FromResidual<CoreResult<Infallible, JustError>> for SignedResult<T> {
+    fn from_residual(result: CoreResult<Infallible, JustError>) -> Self {
+        SignedResult::from_residual(result.map_err(JustErrno::from))
+    }
+}
+
+impl<T: Output> From<SignedResult<T>> for CoreResult<T, ErrnoCode> {
+    fn from(it: SignedResult<T>) -> Self {
+        match it.branch() {
+            ControlFlow::Continue(output) => Ok(output),
+            ControlFlow::Break(errno) => Err(errno),
+        }
+    }
+}
+
+impl<T: Output> From<CoreResult<T, ErrnoCode>> for SignedResult<T> {
+    fn from(result: CoreResult<T, ErrnoCode>) -> Self {
+        match result {
+            Ok(output) => SignedResult::from_output(output),
+            Err(errno) => SignedResult::from_residual(errno),
+        }
+    }
+}
+
+// Compile time check that it costs nothing over the isize.
+const _: () = if core::mem::size_of::<SignedResult>() == core::mem::size_of::<isize>()
+    && core::mem::align_of::<SignedResult>() == core::mem::align_of::<isize>()
+{
+} else {
+    panic!()
+};
+
+fn _test_ok_() -> SignedResult {
+    let n: usize = SignedResult::<usize>::from_raw(3)?;
+    SignedResult::from_output(n + 1)
+}
+
+fn _test_einval_() -> SignedResult {
+    let n: usize = SignedResult::<usize>::from_raw(-22)?;
+    SignedResult::from_output(n + 1)
+}
+
+fn _test_errno_() -> SignedResult {
+    Errno::ENOMEM?;
+    SignedResult::from_output(0)
+}
+
+fn _test_sel4_() -> SignedResult {
+    crate::intersection::Error::FailedLookup?;
+    SignedResult::from_output(0)
+}
+
+// An errno beyond errno-base, through `?`.
+fn _test_unknown_() -> SignedResult {
+    let n: usize = SignedResult::<usize>::from_raw(-MAX_ERRNO)?;
+    SignedResult::from_output(n + 1)
+}
+
+fn _test_core_result_() -> CoreResult<usize, ErrnoCode> {
+    SignedResult::<usize>::from_raw(-34).into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Stands in for the C side, which just sees an isize.
+    extern "C" fn _raw_(raw: isize) -> SignedResult {
+        SignedResult::from_raw(raw)
+    }
+
+    #[test]
+    fn test_ok() {
+        assert_eq!(_test_ok_(), SignedResult::from_raw(4));
+    }
+
+    #[test]
+    fn test_einval() {
+        assert_eq!(_test_einval_(), SignedResult::from_raw(-22));
+        assert_eq!(_test_einval_().errno(), Some(JustErrno::EINVAL.into()));
+    }
+
+    #[test]
+    fn test_errno() {
+        assert_eq!(_test_errno_().errno(), Some(JustErrno::ENOMEM.into()));
+    }
+
+    #[test]
+    fn test_sel4() {
+        assert_eq!(_test_sel4_().errno(), Some(JustErrno::EBADF.into()));
+    }
+
+    #[test]
+    fn test_core_result() {
+        assert_eq!(_test_core_result_(), Err(JustErrno::ERANGE.into()));
+        assert_eq!(SignedResult::from(Ok::<usize, ErrnoCode>(7)).raw(), 7);
+        assert_eq!(
+            SignedResult::<usize>::from(Err(JustErrno::EPERM.into())).raw(),
+            -1
+        );
+    }
+
+    #[test]
+    fn test_error_window() {
+        // The top of the address space is still a success.
+        let address = SignedResult::<usize>::from_raw(-MAX_ERRNO - 1);
+        assert!(!address.is_err());
+        assert_eq!(
+            CoreResult::from(address),
+            Ok::<usize, ErrnoCode>((-MAX_ERRNO - 1) as usize)
+        );
+        // Beyond errno-base is still an error, just not one Errno knows.
+        let unknown = SignedResult::<usize>::from_raw(-MAX_ERRNO);
+        assert_eq!(unknown.errno_code(), Some(MAX_ERRNO as i32));
+        assert_eq!(unknown.errno().and_then(ErrnoCode::errno), None);
+    }
+
+    #[test]
+    fn test_unknown() {
+        // Not EIO, the errno is passed on as it was.
+        assert_eq!(_test_unknown_(), SignedResult::from_raw(-MAX_ERRNO));
+        assert_eq!(
+            CoreResult::<usize, _>::from(_test_unknown_()).map_err(ErrnoCode::code),
+            Err(MAX_ERRNO as i32)
+        );
+        assert_eq!(
+            _test_einval_().errno().and_then(ErrnoCode::errno),
+            Some(JustErrno::EINVAL)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "success value in the errno range")]
+    fn test_output_in_errno_range() {
+        let _ = SignedResult::<usize>::from_output(-5isize as usize);
+    }
+
+    #[test]
+    fn test_ffi_round_trip() {
+        for raw in [
+            0,
+            1,
+            -1,
+            -22,
+            -MAX_ERRNO,
+            -MAX_ERRNO - 1,
+            isize::MAX,
+            isize::MIN,
+        ] {
+            let it = _raw_(raw);
+            assert_eq!(it.raw(), raw);
+            // Safety: SignedResult is repr(transparent) over isize.
+            assert_eq!(unsafe { core::mem::transmute::<_, isize>(it) }, raw);
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use crate::{disjoint, intersection, union};
+use core::fmt::Debug;
+use core::ops::Try;
+use core::result::Result as CoreResult;
+
+//
+// The three implementations behind one API, so code can be written against crate::Code,
+// crate::Error and crate::Result, with the implementation picked by one of the strategy-union,
+// strategy-intersection or strategy-disjoint features.
+//
+// crate::Code is what an extern "C" function returns, the i32 the C side sees,
+// and crate::Error is its Strategy::Error, what is left when `?` on one fails:
+//
+//   strategy-union          union::Error          union::Error
+//   strategy-intersection   intersection::Error   intersection::JustError
+//   strategy-disjoint       disjoint::Result      disjoint::Error
+//
+// crate::Result<T> is Result<T, crate::Error>, for the Rust side, and into_result()? gets
+// one from a Code whichever is picked. Use Strategy's functions rather than naming variants
+// of Code, which differ. The Error's all have the seL4 variants at the seL4 codes,
+// so crate::Error::RangeError is the same whichever is picked, but only the seL4 errors,
+// union's NoError is only there with strategy-union.
+//
+
+#[cfg(any(
+    all(feature = "strategy-union", feature = "strategy-intersection"),
+    all(feature = "strategy-union", feature = "strategy-disjoint"),
+    all(feature = "strategy-intersection", feature = "strategy-disjoint"),
+))]
+compile_error!("Only one of the strategy-union, strategy-intersection and strategy-disjoint features can be enabled");
+
+pub trait Strategy: Copy + Debug + PartialEq + Try {
+    type Error: Copy + Debug;
+
+    const OK: Self;
+
+    fn from_error(error: Self::Error) -> Self;
+
+    fn code(self) -> i32;
+
+    // None for a code which is neither a success nor an error.
+    fn from_code(code: i32) -> Option<Self>;
+
+    // disjoint's NonError's, including the warnings, are all Ok(()).
+    fn into_result(self) -> CoreResult<(), Self::Error>;
+}
+
+impl Strategy for union::Error {
+    type Error = union::Error;
+
+    const OK: Self = union::Error::NoError;
+
+    fn from_error(error: union::Error) -> Self {
+        error
+    }
+
+    fn code(self) -> i32 {
+        self as i32
+    }
+
+    fn from_code(code: i32) -> Option<Self> {
+        union::Error::try_from(code).ok()
+    }
+
+    fn into_result(self) -> CoreResult<(), union::Error> {
+        self.into()
+    }
+}
+
+impl Strategy for intersection::Error {
+    type Error = intersection::JustError;
+
+    const OK: Self = intersection::Error::NoError;
+
+    fn from_error(error: intersection::JustError) -> Self {
+        // Safety: JustError's discriminants are all Error's, see intersection.rs.
+        unsafe { core::mem::transmute::<intersection::JustError, intersection::Error>(error) }
+    }
+
+    fn code(self) -> i32 {
+        self as i32
+    }
+
+    fn from_code(code: i32) -> Option<Self> {
+        intersection::Error::try_from(code).ok()
+    }
+
+    fn into_result(self) -> CoreResult<(), intersection::JustError> {
+        self.into()
+    }
+}
+
+impl Strategy for disjoint::Result {
+    type Error = disjoint::Error;
+
+    const OK: Self = disjoint::Result::OK;
+
+    fn from_error(error: disjoint::Error) -> Self {
+        disjoint::Result::from(Err(error))
+    }
+
+    fn code(self) -> i32 {
+        self.error_code()
+    }
+
+    fn from_code(code: i32) -> Option<Self> {
+        match disjoint::NonError::try_from(code) {
+            Ok(non_error) => Some(disjoint::Result::from(Ok(non_error))),
+            Err(code) => disjoint::Error::try_from(code).ok().map(Self::from_error),
+        }
+    }
+
+    fn into_result(self) -> CoreResult<(), disjoint::Error> {
+        CoreResult::<disjoint::NonError, disjoint::Error>::from(self).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn _propagate_<S: Strategy>(result: S) -> S {
+        let _ = result?;
+        S::OK
+    }
+
+    // The shared suite, run against every strategy, and against crate::Code with a strategy-*
+    // feature, which is what scripts/test-strategies.sh runs for each of them.
+    macro_rules! strategy_tests {
+        ($strategy:ident: $code:ty, $error:ty) => {
+            mod $strategy {
+                use super::*;
+
+                type Code = $code;
+                type Error = $error;
+
+                #[test]
+                fn test_ok() {
+                    assert_eq!(Code::OK.code(), 0);
+                    assert_eq!(Code::from_code(0), Some(Code::OK));
+                    assert!(matches!(Code::OK.into_result(), Ok(())));
+                    assert_eq!(_propagate_(Code::OK), Code::OK);
+                }
+
+                #[test]
+                fn test_error() {
+                    let code = Code::from_error(Error::RangeError);
+                    assert_eq!(code.code(), 4);
+                    assert_eq!(Code::from_code(4), Some(code));
+                    assert!(matches!(code.into_result(), Err(Error::RangeError)));
+                    assert_eq!(_propagate_(code), code);
+                }
+
+                #[test]
+                fn test_codes() {
+                    for error_code in 1..=Error::NotEnoughMemory as i32 {
+                        let code = Code::from_code(error_code).unwrap();
+                        assert_eq!(code.code(), error_code);
+                        let error = code.into_result().unwrap_err();
+                        assert_eq!(Code::from_error(error), code);
+                    }
+                    assert_eq!(Code::from_code(100), None);
+                    assert_eq!(Code::from_code(-100), None);
+                }
+            }
+        };
+    }
+
+    strategy_tests!(test_union: union::Error, union::Error);
+    strategy_tests!(
+        test_intersection: intersection::Error,
+        intersection::JustError
+    );
+    strategy_tests!(test_disjoint: disjoint::Result, disjoint::Error);
+
+    #[cfg(any(
+        feature = "strategy-union",
+        feature = "strategy-intersection",
+        feature = "strategy-disjoint"
+    ))]
+    strategy_tests!(test_selected: crate::Code, crate::Error);
+
+    // The Rust side of a selected strategy, the same source whichever it is.
+    #[cfg(any(
+        feature = "strategy-union",
+        feature = "strategy-intersection",
+        feature = "strategy-disjoint"
+    ))]
+    fn _selected_(first: crate::Code, second: crate::Code) -> crate::Result<i32> {
+        first.into_result()?;
+        second.into_result()?;
+        Ok(second.code())
+    }
+
+    #[cfg(any(
+        feature = "strategy-union",
+        feature = "strategy-intersection",
+        feature = "strategy-disjoint"
+    ))]
+    #[test]
+    fn test_selected_result() {
+        let failed = crate::Code::from_error(crate::Error::InvalidArgument);
+        assert!(matches!(
+            _selected_(crate::Code::OK, crate::Code::OK),
+            Ok(0)
+        ));
+        assert!(matches!(
+            _selected_(crate::Code::OK, failed),
+            Err(crate::Error::InvalidArgument)
+        ));
+        assert!(matches!(
+            _selected_(failed, crate::Code::OK),
+            Err(crate::Error::InvalidArgument)
+        ));
+    }
+}
@@ -0,0 +1,423 @@
+use crate::{disjoint, errno, intersection, repr, union};
+use core::fmt;
+
+//
+// Display for the error enums, out of one static string table rather than a
+// match arm and a string per variant per enum.
+//
+// STRINGS is every name and description concatenated, ENDS is where each of them ends,
+// so an entry costs two u16's on top of its text, rather than two &str's.
+// The table is deduplicated by entry rather than by enum, union::Error, intersection::Error,
+// JustError, the repr enums and disjoint::Error all share the seL4 entries,
+// and Errno shares NoError with them.
+//
+// A description which is the same as an earlier entry's, EINVAL => InvalidArgument,
+// is only in STRINGS once, SHARED sends EINVAL's description to InvalidArgument's.
+//
+// They are statics, so there is the one copy of each in the image, and scripts/size.sh
+// can find them in a release build's symbol table.
+//
+// What ends up in STRINGS is up to the features:
+//   default              "InvalidArgument" and "Invalid argument", Display is the description.
+//   strip-descriptions   just "InvalidArgument", Display is the name.
+//   strip-names          nothing, Display is the error code.
+//
+
+#[cfg(not(feature = "strip-descriptions"))]
+macro_rules! text {
+    ($name:expr, $description:expr) => {
+        concat!($name, $description)
+    };
+}
+
+#[cfg(all(feature = "strip-descriptions", not(feature = "strip-names")))]
+macro_rules! text {
+    ($name:expr, $description:expr) => {
+        $name
+    };
+}
+
+#[cfg(feature = "strip-names")]
+macro_rules! text {
+    ($name:expr, $description:expr) => {
+        ""
+    };
+}
+
+// The description's text, or nothing for one shared with another entry.
+// Unused with strip-descriptions, text! drops the description then.
+#[allow(unused_macros)]
+macro_rules! own_description {
+    ($description:literal) => {
+        $description
+    };
+    ($other:ident) => {
+        ""
+    };
+}
+
+// (entry, other entry) for the descriptions which are shared.
+macro_rules! shared {
+    ([$($done:tt)*]) => {
+        [$($done)*]
+    };
+    ([$($done:tt)*] $entry:ident => $description:literal, $($rest:tt)*) => {
+        shared!([$($done)*] $($rest)*)
+    };
+    ([$($done:tt)*] $entry:ident => $other:ident, $($rest:tt)*) => {
+        shared!([$($done)* (Entry::$entry as u8, Entry::$other as u8),] $($rest)*)
+    };
+}
+
+macro_rules! string_table {
+    ($($entry:ident => $description:tt,)*) => {
+        // The position of each name in the table, and of its description.
+        #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+        #[repr(usize)]
+        #[allow(clippy::upper_case_acronyms)]
+        pub enum Entry {
+            $($entry,)*
+        }
+
+        // Only used at compile time, so none of these end up in the image.
+        const TEXT: &str = concat!($(text!(stringify!($entry), own_description!($description))),*);
+        const LENGTHS: &[usize] = &[
+            $(
+                text!(stringify!($entry), "").len(),
+                text!("", own_description!($description)).len(),
+            )*
+        ];
+        const SHARED_PAIRS: &[(u8, u8)] = &shared!([] $($entry => $description,)*);
+    };
+}
+
+string_table! {
+    NoError => "No error",
+    InvalidArgument => "Invalid argument",
+    InvalidCapability => "Invalid capability",
+    IllegalOperation => "Illegal operation",
+    RangeError => "Argument out of range",
+    AlignmentError => "Misaligned argument",
+    FailedLookup => "Capability lookup failed",
+    TruncatedMessage => "Message truncated",
+    DeleteFirst => "Destination slot occupied",
+    RevokeFirst => "Object has derived capabilities",
+    NotEnoughMemory => "Not enough memory in untyped",
+    // disjoint::Error's, one past NotEnoughMemory as it is in the enum.
+    ErrorCodeOutOfRange => "Error code out of range",
+    Ok => "Ok",
+    OkPartial => "Partially completed",
+    WarnTruncated => "Completed, output truncated",
+    WarnDeprecated => "Completed, using a deprecated operation",
+    EPERM => "Operation not permitted",
+    ENOENT => "No such file or directory",
+    ESRCH => "No such process",
+    EINTR => "Interrupted system call",
+    EIO => "Input/output error",
+    ENXIO => "No such device or address",
+    E2BIG => "Argument list too long",
+    ENOEXEC => "Exec format error",
+    EBADF => "Bad file descriptor",
+    ECHILD => "No child processes",
+    EAGAIN => "Resource temporarily unavailable",
+    ENOMEM => "Cannot allocate memory",
+    EACCES => "Permission denied",
+    EFAULT => "Bad address",
+    ENOTBLK => "Block device required",
+    EBUSY => "Device or resource busy",
+    EEXIST => "File exists",
+    EXDEV => "Invalid cross-device link",
+    ENODEV => "No such device",
+    ENOTDIR => "Not a directory",
+    EISDIR => "Is a directory",
+    EINVAL => InvalidArgument,
+    ENFILE => "Too many open files in system",
+    EMFILE => "Too many open files",
+    ENOTTY => "Inappropriate ioctl for device",
+    ETXTBSY => "Text file busy",
+    EFBIG => "File too large",
+    ENOSPC => "No space left on device",
+    ESPIPE => "Illegal seek",
+    EROFS => "Read-only file system",
+    EMLINK => "Too many links",
+    EPIPE => "Broken pipe",
+    EDOM => "Numerical argument out of domain",
+    ERANGE => "Numerical result out of range",
+}
+
+const fn ends<const N: usize>(lengths: &[usize]) -> [u16; N] {
+    let mut ends = [0; N];
+    let mut end = 0;
+    let mut i = 0;
+    while i < lengths.len() {
+        end += lengths[i];
+        ends[i + 1] = end as u16;
+        i += 1;
+    }
+    ends
+}
+
+const fn bytes<const N: usize>(text: &str) -> [u8; N] {
+    let mut bytes = [0; N];
+    let mut i = 0;
+    while i < N {
+        bytes[i] = text.as_bytes()[i];
+        i += 1;
+    }
+    bytes
+}
+
+const fn pairs<const N: usize>(pairs: &[(u8, u8)]) -> [(u8, u8); N] {
+    let mut array = [(0, 0); N];
+    let mut i = 0;
+    while i < N {
+        array[i] = pairs[i];
+        i += 1;
+    }
+    array
+}
+
+static STRINGS: [u8; TEXT.len()] = bytes(TEXT);
+
+// ENDS[2 * entry] is where the entry's name starts, ENDS[2 * entry + 1] where its description starts.
+static ENDS: [u16; LENGTHS.len() + 1] = ends(LENGTHS);
+
+static SHARED: [(u8, u8); SHARED_PAIRS.len()] = pairs(SHARED_PAIRS);
+
+// Compile time check that the offsets fit in ENDS, and the entries in SHARED.
+const _: () = if TEXT.len() <= u16::MAX as usize && Entry::ERANGE as usize <= u8::MAX as usize {
+} else {
+    panic!()
+};
+
+fn slice(start: usize) -> &'static str {
+    let (start, end) = (ENDS[start] as usize, ENDS[start + 1] as usize);
+    // Safety: STRINGS is TEXT, every offset in ENDS is at the end of a literal in it,
+    // so on a char boundary, and ENDS is non-decreasing.
+    unsafe { core::str::from_utf8_unchecked(STRINGS.get_unchecked(start..end)) }
+}
+
+// "" with strip-names.
+pub fn name(entry: usize) -> &'static str {
+    slice(2 * entry)
+}
+
+// "" with strip-descriptions.
+pub fn description(entry: usize) -> &'static str {
+    let entry = SHARED
+        .iter()
+        .find(|(shared, _)| *shared as usize == entry)
+        .map_or(entry, |(_, other)| *other as usize);
+    slice(2 * entry + 1)
+}
+
+pub trait Described: Sized + Copy {
+    fn entry(self) -> usize;
+
+    fn description(self) -> &'static str {
+        description(self.entry())
+    }
+}
+
+// $first is at $entry, and the rest of the discriminants follow on from it in the table.
+// Otherwise |it| the entry of it.
+macro_rules! impl_described {
+    ($ty:ty, $first:ident => $entry:ident) => {
+        impl_described!($ty, |it| Entry::$entry as usize
+            + (it as usize - <$ty>::$first as usize));
+    };
+    ($ty:ty, |$it:ident| $index:expr) => {
+        impl Described for $ty {
+            fn entry(self) -> usize {
+                let $it = self;
+                $index
+            }
+        }
+
+        impl fmt::Display for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                if cfg!(feature = "strip-names") {
+                    write!(f, "error {}", *self as i64)
+                } else if cfg!(feature = "strip-descriptions") {
+                    f.write_str(name(self.entry()))
+                } else {
+                    f.write_str(description(self.entry()))
+                }
+            }
+        }
+    };
+}
+
+impl_described!(union::Error, NoError => NoError);
+impl_described!(intersection::Error, NoError => NoError);
+impl_described!(intersection::JustError, InvalidArgument => InvalidArgument);
+impl_described!(repr::Error8, NoError => NoError);
+impl_described!(repr::JustError8, InvalidArgument => InvalidArgument);
+impl_described!(repr::ErrorWord, NoError => NoError);
+impl_described!(disjoint::Error, InvalidArgument => InvalidArgument);
+// Counting down from Ok.
+impl_described!(disjoint::NonError, |it| Entry::Ok as usize
+    + (disjoint::NonError::Ok as i32 - it as i32) as usize);
+impl_described!(errno::Errno, |it| match it {
+    errno::Errno::NoError => Entry::NoError as usize,
+    _ => Entry::EPERM as usize + (it as usize - errno::Errno::EPERM as usize),
+});
+impl_described!(errno::JustErrno, EPERM => EPERM);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::names::Named;
+    use core::fmt::Debug;
+    use core::result::Result as CoreResult;
+    use std::string::ToString;
+    use strum::EnumCount;
+
+    // Every variant of every enum, against the Debug derive's names.
+    fn check<T: Described + Named + Debug>(it: T) {
+        #[cfg(not(feature = "strip-names"))]
+        {
+            assert_eq!(name(it.entry()), std::format!("{:?}", it));
+            assert_eq!(
+                T::from_name(name(it.entry())).map(T::entry),
+                Some(it.entry())
+            );
+        }
+        #[cfg(feature = "strip-names")]
+        assert_eq!(name(it.entry()), "");
+    }
+
+    #[test]
+    fn test_table() {
+        for error_code in 0..union::Error::COUNT as i32 {
+            check(union::Error::try_from(error_code).unwrap());
+            check(intersection::Error::try_from(error_code).unwrap());
+            check(repr::Error8::try_from(error_code as u8).unwrap());
+            check(repr::ErrorWord::try_from(error_code as usize).unwrap());
+            if let Err(just_error) = CoreResult::<(), intersection::JustError>::from(
+                intersection::Error::try_from(error_code).unwrap(),
+            ) {
+                check(just_error);
+            }
+            if let Err(just_error) = CoreResult::<(), repr::JustError8>::from(
+                repr::Error8::try_from(error_code as u8).unwrap(),
+            ) {
+                check(just_error);
+            }
+        }
+        for error_code in
+            disjoint::Error::InvalidArgument as i32..=disjoint::Error::ErrorCodeOutOfRange as i32
+        {
+            check(disjoint::Error::try_from(error_code).unwrap());
+        }
+        for non_error in [
+            disjoint::NonError::Ok,
+            disjoint::NonError::OkPartial,
+            disjoint::NonError::WarnTruncated,
+            disjoint::NonError::WarnDeprecated,
+        ] {
+            check(non_error);
+        }
+        for error_code in 0..errno::Errno::COUNT as i32 {
+            let errno = errno::Errno::try_from(error_code).unwrap();
+            check(errno);
+            if let Err(just_errno) = CoreResult::<(), errno::JustErrno>::from(errno) {
+                check(just_errno);
+            }
+        }
+    }
+
+    #[test]
+    fn test_shared() {
+        assert_eq!(union::Error::RangeError.entry(), Entry::RangeError as usize);
+        assert_eq!(
+            intersection::JustError::RangeError.entry(),
+            Entry::RangeError as usize
+        );
+        assert_eq!(
+            repr::JustError8::RangeError.entry(),
+            Entry::RangeError as usize
+        );
+        assert_eq!(
+            disjoint::Error::RangeError.entry(),
+            Entry::RangeError as usize
+        );
+        assert_eq!(errno::Errno::NoError.entry(), union::Error::NoError.entry());
+        // Nothing is in the table twice, names or descriptions.
+        #[cfg(not(feature = "strip-names"))]
+        {
+            let names: std::collections::HashSet<_> = (0..ENDS.len() / 2).map(name).collect();
+            assert_eq!(names.len(), ENDS.len() / 2);
+        }
+        for entry in 0..ENDS.len() / 2 {
+            for other in 0..entry {
+                let (a, b) = (description(entry), description(other));
+                if a == b && !a.is_empty() {
+                    assert_eq!(
+                        a.as_ptr(),
+                        b.as_ptr(),
+                        "{} and {}",
+                        name(entry),
+                        name(other)
+                    );
+                }
+            }
+        }
+        #[cfg(not(feature = "strip-descriptions"))]
+        assert_eq!(
+            description(Entry::EINVAL as usize).as_ptr(),
+            description(Entry::InvalidArgument as usize).as_ptr()
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        #[cfg(not(feature = "strip-descriptions"))]
+        {
+            assert_eq!(intersection::Error::NoError.to_string(), "No error");
+            assert_eq!(
+                disjoint::NonError::OkPartial.to_string(),
+                "Partially completed"
+            );
+            assert_eq!(errno::JustErrno::EINVAL.to_string(), "Invalid argument");
+            assert_eq!(
+                union::Error::FailedLookup.description(),
+                "Capability lookup failed"
+            );
+        }
+        #[cfg(all(feature = "strip-descriptions", not(feature = "strip-names")))]
+        {
+            assert_eq!(intersection::Error::NoError.to_string(), "NoError");
+            assert_eq!(errno::JustErrno::EINVAL.to_string(), "EINVAL");
+            assert_eq!(union::Error::FailedLookup.description(), "");
+        }
+        #[cfg(feature = "strip-names")]
+        {
+            assert_eq!(intersection::Error::RangeError.to_string(), "error 4");
+            assert_eq!(disjoint::NonError::OkPartial.to_string(), "error -1");
+        }
+    }
+
+    // What Display costs in the image is these three, and one fmt per enum,
+    // scripts/size.sh checks what is left of them in a release build.
+    #[test]
+    fn test_size() {
+        let strings = core::mem::size_of_val(&STRINGS);
+        let ends = core::mem::size_of_val(&ENDS);
+        let shared = core::mem::size_of_val(&SHARED);
+        assert_eq!(strings, TEXT.len());
+        assert_eq!(shared, 2 * SHARED_PAIRS.len());
+        // Entries, not pointers.
+        assert_eq!(ends, 2 * (2 * Entry::ERANGE as usize + 3));
+        let table = strings + ends + shared;
+        let budget = if cfg!(not(feature = "strip-descriptions")) {
+            2048
+        } else if cfg!(not(feature = "strip-names")) {
+            768
+        } else {
+            // Just ENDS and SHARED.
+            ends + shared
+        };
+        assert!(table <= budget, "{} bytes", table);
+    }
+}
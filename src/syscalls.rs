@@ -0,0 +1,293 @@
+use crate::intersection::{Error, JustError};
+use core::result::Result as CoreResult;
+
+// The actual kernel calls, only available when building for seL4 itself.
+#[cfg(feature = "sel4")]
+pub mod sel4;
+
+// An in-process stand in for the kernel, this is what a Linux build uses.
+// It is on by default, an seL4 build should turn off default features.
+#[cfg(feature = "mock")]
+pub mod mock;
+
+#[cfg(feature = "sel4")]
+pub type DefaultKernel = sel4::Sel4;
+
+#[cfg(all(feature = "mock", not(feature = "sel4")))]
+pub type DefaultKernel = mock::MockKernel;
+
+pub type Word = usize;
+pub type CPtr = Word;
+
+// seL4_CapNull
+pub const CAP_NULL: CPtr = 0;
+
+// seL4_CapRights_t, a bitfield of
+// AllowGrantReply | AllowGrant | AllowRead | AllowWrite
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct CapRights(pub Word);
+
+impl CapRights {
+    pub const NONE: CapRights = CapRights(0);
+    pub const ALL: CapRights = CapRights(0xf);
+}
+
+// Only the architecture independent object types, these have the same values on every
+// architecture, the architecture specific ones follow after CapTable.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(usize)]
+pub enum ObjectType {
+    Untyped = 0,
+    Tcb,
+    Endpoint,
+    Notification,
+    CapTable,
+}
+
+impl ObjectType {
+    pub fn from_word(word: Word) -> Option<ObjectType> {
+        match word {
+            0 => Some(ObjectType::Untyped),
+            1 => Some(ObjectType::Tcb),
+            2 => Some(ObjectType::Endpoint),
+            3 => Some(ObjectType::Notification),
+            4 => Some(ObjectType::CapTable),
+            _ => None,
+        }
+    }
+}
+
+// The raw invocations, these return the C ABI compatible Error exactly as the kernel does.
+//
+// Argument order and naming follows libsel4, with the service capability first.
+pub trait Kernel {
+    #[allow(clippy::too_many_arguments)]
+    fn untyped_retype(
+        &mut self,
+        service: CPtr,
+        type_: Word,
+        size_bits: Word,
+        root: CPtr,
+        node_index: Word,
+        node_depth: Word,
+        node_offset: Word,
+        num_objects: Word,
+    ) -> Error;
+
+    #[allow(clippy::too_many_arguments)]
+    fn cnode_copy(
+        &mut self,
+        service: CPtr,
+        dest_index: Word,
+        dest_depth: u8,
+        src_root: CPtr,
+        src_index: Word,
+        src_depth: u8,
+        rights: CapRights,
+    ) -> Error;
+
+    fn cnode_delete(&mut self, service: CPtr, index: Word, depth: u8) -> Error;
+
+    #[allow(clippy::too_many_arguments)]
+    fn tcb_configure(
+        &mut self,
+        service: CPtr,
+        fault_ep: Word,
+        cspace_root: CPtr,
+        cspace_root_data: Word,
+        vspace_root: CPtr,
+        vspace_root_data: Word,
+        buffer: Word,
+        buffer_frame: CPtr,
+    ) -> Error;
+}
+
+// The fault_point! sites of the wrappers below.
+pub mod site {
+    pub const UNTYPED_RETYPE: u32 = 1;
+    pub const CNODE_COPY: u32 = 2;
+    pub const CNODE_DELETE: u32 = 3;
+    pub const TCB_CONFIGURE: u32 = 4;
+}
+
+// The wrappers, these are the only thing most code should call.
+//
+// The `?` here is doing the Error -> Result<(), JustError> conversion.
+
+#[allow(clippy::too_many_arguments)]
+pub fn untyped_retype<K: Kernel>(
+    kernel: &mut K,
+    service: CPtr,
+    type_: ObjectType,
+    size_bits: Word,
+    root: CPtr,
+    node_index: Word,
+    node_depth: Word,
+    node_offset: Word,
+    num_objects: Word,
+) -> CoreResult<(), JustError> {
+    let error = kernel.untyped_retype(
+        service,
+        type_ as Word,
+        size_bits,
+        root,
+        node_index,
+        node_depth,
+        node_offset,
+        num_objects,
+    );
+    Ok(crate::fault_point!(site::UNTYPED_RETYPE, error)?)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn cnode_copy<K: Kernel>(
+    kernel: &mut K,
+    service: CPtr,
+    dest_index: Word,
+    dest_depth: u8,
+    src_root: CPtr,
+    src_index: Word,
+    src_depth: u8,
+    rights: CapRights,
+) -> CoreResult<(), JustError> {
+    let error = kernel.cnode_copy(
+        service, dest_index, dest_depth, src_root, src_index, src_depth, rights,
+    );
+    Ok(crate::fault_point!(site::CNODE_COPY, error)?)
+}
+
+pub fn cnode_delete<K: Kernel>(
+    kernel: &mut K,
+    service: CPtr,
+    index: Word,
+    depth: u8,
+) -> CoreResult<(), JustError> {
+    let error = kernel.cnode_delete(service, index, depth);
+    Ok(crate::fault_point!(site::CNODE_DELETE, error)?)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn tcb_configure<K: Kernel>(
+    kernel: &mut K,
+    service: CPtr,
+    fault_ep: Word,
+    cspace_root: CPtr,
+    cspace_root_data: Word,
+    vspace_root: CPtr,
+    vspace_root_data: Word,
+    buffer: Word,
+    buffer_frame: CPtr,
+) -> CoreResult<(), JustError> {
+    let error = kernel.tcb_configure(
+        service,
+        fault_ep,
+        cspace_root,
+        cspace_root_data,
+        vspace_root,
+        vspace_root_data,
+        buffer,
+        buffer_frame,
+    );
+    Ok(crate::fault_point!(site::TCB_CONFIGURE, error)?)
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod test {
+    use super::mock::*;
+    use super::*;
+
+    const DEPTH: u8 = RADIX;
+
+    fn _copy_then_delete_(kernel: &mut MockKernel) -> CoreResult<(), JustError> {
+        cnode_copy(
+            kernel,
+            INIT_THREAD_CNODE,
+            10,
+            DEPTH,
+            INIT_THREAD_CNODE,
+            INIT_THREAD_TCB,
+            DEPTH,
+            CapRights::ALL,
+        )?;
+        cnode_delete(kernel, INIT_THREAD_CNODE, 10, DEPTH)?;
+        Ok(())
+    }
+
+    fn _copy_twice_(kernel: &mut MockKernel) -> CoreResult<(), JustError> {
+        for _ in 0..2 {
+            cnode_copy(
+                kernel,
+                INIT_THREAD_CNODE,
+                10,
+                DEPTH,
+                INIT_THREAD_CNODE,
+                INIT_THREAD_TCB,
+                DEPTH,
+                CapRights::ALL,
+            )?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_then_delete() -> CoreResult<(), JustError> {
+        let mut kernel = MockKernel::new();
+        _copy_then_delete_(&mut kernel)?;
+        assert_eq!(kernel.slot(10), Cap::Null);
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_twice() {
+        let mut kernel = MockKernel::new();
+        assert_eq!(_copy_twice_(&mut kernel), Err(JustError::DeleteFirst));
+    }
+
+    #[test]
+    fn test_retype_then_configure() -> CoreResult<(), JustError> {
+        let mut kernel = MockKernel::new();
+        kernel.insert(20, Cap::untyped(16))?;
+        untyped_retype(
+            &mut kernel,
+            20,
+            ObjectType::Tcb,
+            0,
+            INIT_THREAD_CNODE,
+            0,
+            0,
+            21,
+            1,
+        )?;
+        tcb_configure(
+            &mut kernel,
+            21,
+            CAP_NULL,
+            INIT_THREAD_CNODE,
+            0,
+            INIT_THREAD_VSPACE,
+            0,
+            0,
+            CAP_NULL,
+        )
+    }
+
+    #[test]
+    fn test_configure_not_a_tcb() {
+        let mut kernel = MockKernel::new();
+        assert_eq!(
+            tcb_configure(
+                &mut kernel,
+                INIT_THREAD_CNODE,
+                CAP_NULL,
+                INIT_THREAD_CNODE,
+                0,
+                INIT_THREAD_VSPACE,
+                0,
+                0,
+                CAP_NULL,
+            ),
+            Err(JustError::IllegalOperation)
+        );
+    }
+}
@@ -0,0 +1,709 @@
+use super::{CPtr, CapRights, Kernel, ObjectType, Word};
+use crate::intersection::{Error, JustError};
+use core::result::Result as CoreResult;
+
+//
+// A single level CSpace and untyped allocator standing in for the kernel,
+// the checks are made in the same order as the kernel makes them, so you get the same
+// error the kernel would give you for the same invocation.
+//
+// There is exactly one CNode, the root CNode of the initial thread.
+// Every cptr is resolved directly as an index into it, so the only depth which
+// resolves is RADIX. Nested CNodes are not modelled, retyping a CapTable gives you
+// a capability, but it can't be used as the destination of anything.
+//
+// Each slot remembers the slot it was derived from, which is enough of the
+// mapping database to get RevokeFirst and the untyped reset right.
+//
+// TruncatedMessage is the one error it never returns, the wrappers always marshal the
+// whole message.
+//
+// The lookups return Result<_, JustError> so that the invocations can just use `?`
+// and still return the C ABI compatible Error.
+//
+
+pub const RADIX: u8 = 6;
+pub const SLOTS: usize = 1 << RADIX;
+
+// The same slots the kernel hands the initial thread.
+pub const INIT_THREAD_TCB: CPtr = 1;
+pub const INIT_THREAD_CNODE: CPtr = 2;
+pub const INIT_THREAD_VSPACE: CPtr = 3;
+
+// The sizes are the ones for 64 bit configurations.
+pub const WORD_BITS: Word = 64;
+pub const SLOT_BITS: Word = 5;
+pub const TCB_BITS: Word = 11;
+pub const ENDPOINT_BITS: Word = 4;
+pub const NOTIFICATION_BITS: Word = 5;
+pub const MIN_UNTYPED_BITS: Word = 4;
+pub const MAX_UNTYPED_BITS: Word = 47;
+pub const IPC_BUFFER_SIZE_BITS: Word = 10;
+pub const RETYPE_FAN_OUT_LIMIT: Word = 256;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Cap {
+    Null,
+    // free_index is in bytes, rather than the kernel's shifted representation.
+    Untyped { size_bits: Word, free_index: Word },
+    Tcb,
+    Endpoint,
+    Notification,
+    CNode,
+    CapTable { radix: Word },
+    // These two can't be retyped by the mock, use MockKernel::insert
+    // as you would find them in bootinfo.
+    Frame,
+    VSpace,
+}
+
+impl Cap {
+    pub const fn untyped(size_bits: Word) -> Cap {
+        Cap::Untyped {
+            size_bits,
+            free_index: 0,
+        }
+    }
+
+    fn from_object(type_: ObjectType, size_bits: Word) -> Cap {
+        match type_ {
+            ObjectType::Untyped => Cap::untyped(size_bits),
+            ObjectType::Tcb => Cap::Tcb,
+            ObjectType::Endpoint => Cap::Endpoint,
+            ObjectType::Notification => Cap::Notification,
+            ObjectType::CapTable => Cap::CapTable { radix: size_bits },
+        }
+    }
+}
+
+fn object_size_bits(type_: ObjectType, size_bits: Word) -> Word {
+    match type_ {
+        ObjectType::Untyped => size_bits,
+        ObjectType::Tcb => TCB_BITS,
+        ObjectType::Endpoint => ENDPOINT_BITS,
+        ObjectType::Notification => NOTIFICATION_BITS,
+        ObjectType::CapTable => size_bits.saturating_add(SLOT_BITS),
+    }
+}
+
+const fn align_up(value: Word, bits: Word) -> Word {
+    let mask = (1 << bits) - 1;
+    (value + mask) & !mask
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Slot {
+    cap: Cap,
+    parent: Option<usize>,
+}
+
+const EMPTY: Slot = Slot {
+    cap: Cap::Null,
+    parent: None,
+};
+
+pub struct MockKernel {
+    slots: [Slot; SLOTS],
+}
+
+impl Default for MockKernel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockKernel {
+    pub fn new() -> Self {
+        let mut slots = [EMPTY; SLOTS];
+        slots[INIT_THREAD_TCB].cap = Cap::Tcb;
+        slots[INIT_THREAD_CNODE].cap = Cap::CNode;
+        slots[INIT_THREAD_VSPACE].cap = Cap::VSpace;
+        MockKernel { slots }
+    }
+
+    pub fn slot(&self, cptr: CPtr) -> Cap {
+        self.slots.get(cptr).map_or(Cap::Null, |slot| slot.cap)
+    }
+
+    // Caps inserted this way have no parent, like the ones in bootinfo.
+    // An untyped has to be a size the kernel could have handed out.
+    pub fn insert(&mut self, cptr: CPtr, cap: Cap) -> Error {
+        if let Cap::Untyped { size_bits, .. } = cap {
+            if !(MIN_UNTYPED_BITS..=MAX_UNTYPED_BITS).contains(&size_bits) {
+                return Error::RangeError;
+            }
+        }
+        let slot = self.lookup_slot(cptr, RADIX as Word)?;
+        self.ensure_empty(slot)?;
+        self.slots[slot] = Slot { cap, parent: None };
+        Error::NoError
+    }
+
+    // The invoked capability, trying to invoke a null cap is an InvalidCapability.
+    fn invoked(&self, service: CPtr) -> CoreResult<Cap, JustError> {
+        match self.slots.get(service) {
+            None | Some(Slot { cap: Cap::Null, .. }) => Err(JustError::InvalidCapability),
+            Some(slot) => Ok(slot.cap),
+        }
+    }
+
+    // Extra caps passed along with an invocation, these are looked up but not type checked.
+    fn extra_cap(&self, cptr: CPtr) -> CoreResult<Cap, JustError> {
+        self.slots
+            .get(cptr)
+            .map(|slot| slot.cap)
+            .ok_or(JustError::FailedLookup)
+    }
+
+    fn lookup_slot(&self, index: Word, depth: Word) -> CoreResult<usize, JustError> {
+        if !(1..=WORD_BITS).contains(&depth) {
+            Err(JustError::RangeError)
+        } else if depth != RADIX as Word || index >= SLOTS {
+            Err(JustError::FailedLookup)
+        } else {
+            Ok(index)
+        }
+    }
+
+    fn ensure_empty(&self, slot: usize) -> CoreResult<(), JustError> {
+        match self.slots[slot].cap {
+            Cap::Null => Ok(()),
+            _ => Err(JustError::DeleteFirst),
+        }
+    }
+
+    fn has_children(&self, slot: usize) -> bool {
+        self.slots.iter().any(|it| it.parent == Some(slot))
+    }
+
+    // An untyped can only be derived from if nothing has been derived from it yet.
+    fn derive(&self, slot: usize) -> CoreResult<Cap, JustError> {
+        match self.slots[slot].cap {
+            Cap::Untyped { .. } if self.has_children(slot) => Err(JustError::RevokeFirst),
+            cap => Ok(cap),
+        }
+    }
+}
+
+impl Kernel for MockKernel {
+    fn untyped_retype(
+        &mut self,
+        service: CPtr,
+        type_: Word,
+        size_bits: Word,
+        root: CPtr,
+        node_index: Word,
+        node_depth: Word,
+        node_offset: Word,
+        num_objects: Word,
+    ) -> Error {
+        let (untyped_bits, free_index) = match self.invoked(service)? {
+            Cap::Untyped {
+                size_bits,
+                free_index,
+            } => (size_bits, free_index),
+            _ => return Error::IllegalOperation,
+        };
+        let type_ = match ObjectType::from_word(type_) {
+            Some(type_) => type_,
+            None => return Error::InvalidArgument,
+        };
+        let object_bits = object_size_bits(type_, size_bits);
+        if size_bits >= WORD_BITS || object_bits > MAX_UNTYPED_BITS {
+            return Error::RangeError;
+        }
+        match type_ {
+            ObjectType::CapTable if size_bits == 0 => return Error::InvalidArgument,
+            ObjectType::Untyped if size_bits < MIN_UNTYPED_BITS => return Error::InvalidArgument,
+            _ => (),
+        }
+        // A depth of 0 means root is itself the destination CNode.
+        let dest = if node_depth == 0 {
+            self.extra_cap(root)?
+        } else {
+            let slot = self.lookup_slot(node_index, node_depth)?;
+            self.slots[slot].cap
+        };
+        if dest != Cap::CNode {
+            return Error::FailedLookup;
+        }
+        if !(1..=RETYPE_FAN_OUT_LIMIT).contains(&num_objects) {
+            return Error::RangeError;
+        }
+        if node_offset > SLOTS - 1 || num_objects > SLOTS - node_offset {
+            return Error::RangeError;
+        }
+        let window = node_offset..node_offset + num_objects;
+        for slot in window.clone() {
+            self.ensure_empty(slot)?;
+        }
+        // Once everything derived from it is gone, the untyped starts over from the beginning.
+        let free_index = if self.has_children(service) {
+            free_index
+        } else {
+            0
+        };
+        // insert() already checks the size, this is so a bad one can't overflow the shift.
+        let untyped_bytes = match u32::try_from(untyped_bits)
+            .ok()
+            .and_then(|bits| Word::checked_shl(1, bits))
+        {
+            Some(untyped_bytes) => untyped_bytes,
+            None => return Error::RangeError,
+        };
+        let start = align_up(free_index, object_bits);
+        let free_bytes = untyped_bytes - start.min(untyped_bytes);
+        if (free_bytes >> object_bits) < num_objects {
+            return Error::NotEnoughMemory;
+        }
+        for slot in window {
+            self.slots[slot] = Slot {
+                cap: Cap::from_object(type_, size_bits),
+                parent: Some(service),
+            };
+        }
+        self.slots[service].cap = Cap::Untyped {
+            size_bits: untyped_bits,
+            free_index: start + (num_objects << object_bits),
+        };
+        Error::NoError
+    }
+
+    fn cnode_copy(
+        &mut self,
+        service: CPtr,
+        dest_index: Word,
+        dest_depth: u8,
+        src_root: CPtr,
+        src_index: Word,
+        src_depth: u8,
+        _rights: CapRights,
+    ) -> Error {
+        if self.invoked(service)? != Cap::CNode {
+            return Error::IllegalOperation;
+        }
+        let dest = self.lookup_slot(dest_index, dest_depth as Word)?;
+        self.ensure_empty(dest)?;
+        if self.extra_cap(src_root)? != Cap::CNode {
+            return Error::FailedLookup;
+        }
+        let src = self.lookup_slot(src_index, src_depth as Word)?;
+        if self.slots[src].cap == Cap::Null {
+            return Error::FailedLookup;
+        }
+        let cap = self.derive(src)?;
+        self.slots[dest] = Slot {
+            cap,
+            parent: Some(src),
+        };
+        Error::NoError
+    }
+
+    fn cnode_delete(&mut self, service: CPtr, index: Word, depth: u8) -> Error {
+        if self.invoked(service)? != Cap::CNode {
+            return Error::IllegalOperation;
+        }
+        let slot = self.lookup_slot(index, depth as Word)?;
+        // Anything derived from it now belongs to whatever it was derived from.
+        let parent = self.slots[slot].parent;
+        for it in self.slots.iter_mut() {
+            if it.parent == Some(slot) {
+                it.parent = parent;
+            }
+        }
+        self.slots[slot] = EMPTY;
+        Error::NoError
+    }
+
+    fn tcb_configure(
+        &mut self,
+        service: CPtr,
+        _fault_ep: Word,
+        cspace_root: CPtr,
+        _cspace_root_data: Word,
+        vspace_root: CPtr,
+        _vspace_root_data: Word,
+        buffer: Word,
+        buffer_frame: CPtr,
+    ) -> Error {
+        if self.invoked(service)? != Cap::Tcb {
+            return Error::IllegalOperation;
+        }
+        let cspace = self.extra_cap(cspace_root)?;
+        let vspace = self.extra_cap(vspace_root)?;
+        let frame = self.extra_cap(buffer_frame)?;
+        // A buffer address of 0 means no IPC buffer, and the frame is ignored.
+        if buffer != 0 {
+            if frame != Cap::Frame {
+                return Error::IllegalOperation;
+            }
+            if buffer & ((1 << IPC_BUFFER_SIZE_BITS) - 1) != 0 {
+                return Error::AlignmentError;
+            }
+        }
+        if cspace != Cap::CNode {
+            return Error::IllegalOperation;
+        }
+        if vspace != Cap::VSpace {
+            return Error::IllegalOperation;
+        }
+        Error::NoError
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::syscalls::{cnode_copy, cnode_delete, tcb_configure, untyped_retype, CAP_NULL};
+
+    const DEPTH: u8 = RADIX;
+    const UNTYPED: CPtr = 20;
+
+    fn with_untyped(size_bits: Word) -> MockKernel {
+        let mut kernel = MockKernel::new();
+        assert_eq!(
+            kernel.insert(UNTYPED, Cap::untyped(size_bits)),
+            Error::NoError
+        );
+        kernel
+    }
+
+    fn _retype_(
+        kernel: &mut MockKernel,
+        type_: ObjectType,
+        size_bits: Word,
+        offset: Word,
+        count: Word,
+    ) -> CoreResult<(), JustError> {
+        untyped_retype(
+            kernel,
+            UNTYPED,
+            type_,
+            size_bits,
+            INIT_THREAD_CNODE,
+            0,
+            0,
+            offset,
+            count,
+        )
+    }
+
+    // Retype a TCB, give it a buffer, then throw the lot away and do it again.
+    fn _tcb_with_buffer_(kernel: &mut MockKernel, buffer: Word) -> CoreResult<(), JustError> {
+        _retype_(kernel, ObjectType::Tcb, 0, 30, 1)?;
+        tcb_configure(
+            kernel,
+            30,
+            CAP_NULL,
+            INIT_THREAD_CNODE,
+            0,
+            INIT_THREAD_VSPACE,
+            0,
+            buffer,
+            31,
+        )?;
+        cnode_delete(kernel, INIT_THREAD_CNODE, 30, DEPTH)?;
+        _retype_(kernel, ObjectType::Tcb, 0, 30, 1)
+    }
+
+    #[test]
+    fn test_invoke_null() {
+        let mut kernel = MockKernel::new();
+        assert_eq!(kernel.cnode_delete(40, 10, DEPTH), Error::InvalidCapability);
+    }
+
+    #[test]
+    fn test_invoke_wrong_type() {
+        let mut kernel = MockKernel::new();
+        assert_eq!(
+            kernel.cnode_delete(INIT_THREAD_TCB, 10, DEPTH),
+            Error::IllegalOperation
+        );
+    }
+
+    #[test]
+    fn test_depth_mismatch() {
+        let mut kernel = MockKernel::new();
+        assert_eq!(
+            kernel.cnode_delete(INIT_THREAD_CNODE, 10, DEPTH - 1),
+            Error::FailedLookup
+        );
+    }
+
+    #[test]
+    fn test_depth_out_of_range() {
+        let mut kernel = MockKernel::new();
+        assert_eq!(
+            kernel.cnode_delete(INIT_THREAD_CNODE, 10, 0),
+            Error::RangeError
+        );
+        assert_eq!(
+            kernel.cnode_delete(INIT_THREAD_CNODE, 10, WORD_BITS as u8 + 1),
+            Error::RangeError
+        );
+    }
+
+    #[test]
+    fn test_index_out_of_range() {
+        let mut kernel = MockKernel::new();
+        assert_eq!(
+            kernel.cnode_delete(INIT_THREAD_CNODE, SLOTS, DEPTH),
+            Error::FailedLookup
+        );
+    }
+
+    #[test]
+    fn test_copy_empty_source() {
+        let mut kernel = MockKernel::new();
+        assert_eq!(
+            kernel.cnode_copy(
+                INIT_THREAD_CNODE,
+                10,
+                DEPTH,
+                INIT_THREAD_CNODE,
+                11,
+                DEPTH,
+                CapRights::ALL
+            ),
+            Error::FailedLookup
+        );
+    }
+
+    #[test]
+    fn test_retype_invalid_type() {
+        let mut kernel = with_untyped(16);
+        assert_eq!(
+            kernel.untyped_retype(UNTYPED, 100, 0, INIT_THREAD_CNODE, 0, 0, 21, 1),
+            Error::InvalidArgument
+        );
+    }
+
+    #[test]
+    fn test_retype_invalid_size() {
+        let mut kernel = with_untyped(16);
+        assert_eq!(
+            _retype_(&mut kernel, ObjectType::CapTable, 0, 21, 1),
+            Err(JustError::InvalidArgument)
+        );
+        assert_eq!(
+            _retype_(
+                &mut kernel,
+                ObjectType::Untyped,
+                MIN_UNTYPED_BITS - 1,
+                21,
+                1
+            ),
+            Err(JustError::InvalidArgument)
+        );
+        assert_eq!(
+            _retype_(
+                &mut kernel,
+                ObjectType::Untyped,
+                MAX_UNTYPED_BITS + 1,
+                21,
+                1
+            ),
+            Err(JustError::RangeError)
+        );
+        assert_eq!(
+            _retype_(&mut kernel, ObjectType::CapTable, MAX_UNTYPED_BITS, 21, 1),
+            Err(JustError::RangeError)
+        );
+    }
+
+    #[test]
+    fn test_insert_untyped_size() {
+        let mut kernel = MockKernel::new();
+        for size_bits in [
+            MIN_UNTYPED_BITS - 1,
+            MAX_UNTYPED_BITS + 1,
+            WORD_BITS,
+            Word::MAX,
+        ] {
+            assert_eq!(
+                kernel.insert(UNTYPED, Cap::untyped(size_bits)),
+                Error::RangeError
+            );
+        }
+        assert_eq!(kernel.slot(UNTYPED), Cap::Null);
+        assert_eq!(
+            kernel.insert(UNTYPED, Cap::untyped(MAX_UNTYPED_BITS)),
+            Error::NoError
+        );
+        assert_eq!(
+            _retype_(&mut kernel, ObjectType::Untyped, MAX_UNTYPED_BITS, 21, 1),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_retype_window() {
+        let mut kernel = with_untyped(16);
+        assert_eq!(
+            _retype_(&mut kernel, ObjectType::Endpoint, 0, SLOTS - 1, 2),
+            Err(JustError::RangeError)
+        );
+        assert_eq!(
+            _retype_(&mut kernel, ObjectType::Endpoint, 0, 21, 0),
+            Err(JustError::RangeError)
+        );
+        assert_eq!(
+            _retype_(&mut kernel, ObjectType::Endpoint, 0, 19, 2),
+            Err(JustError::DeleteFirst)
+        );
+    }
+
+    #[test]
+    fn test_retype_not_enough_memory() {
+        // Room for exactly two TCBs.
+        let mut kernel = with_untyped(TCB_BITS + 1);
+        assert_eq!(
+            _retype_(&mut kernel, ObjectType::Tcb, 0, 21, 3),
+            Err(JustError::NotEnoughMemory)
+        );
+        assert_eq!(_retype_(&mut kernel, ObjectType::Tcb, 0, 21, 2), Ok(()));
+        assert_eq!(
+            _retype_(&mut kernel, ObjectType::Endpoint, 0, 23, 1),
+            Err(JustError::NotEnoughMemory)
+        );
+    }
+
+    #[test]
+    fn test_retype_alignment() {
+        // An endpoint followed by a TCB has to skip ahead to the next TCB boundary.
+        let mut kernel = with_untyped(TCB_BITS + 1);
+        assert_eq!(
+            _retype_(&mut kernel, ObjectType::Endpoint, 0, 21, 1),
+            Ok(())
+        );
+        assert_eq!(_retype_(&mut kernel, ObjectType::Tcb, 0, 22, 1), Ok(()));
+        assert_eq!(
+            _retype_(&mut kernel, ObjectType::Tcb, 0, 23, 1),
+            Err(JustError::NotEnoughMemory)
+        );
+    }
+
+    #[test]
+    fn test_retype_reset() {
+        let mut kernel = with_untyped(TCB_BITS);
+        assert_eq!(_retype_(&mut kernel, ObjectType::Tcb, 0, 21, 1), Ok(()));
+        assert_eq!(
+            _retype_(&mut kernel, ObjectType::Tcb, 0, 22, 1),
+            Err(JustError::NotEnoughMemory)
+        );
+        assert_eq!(
+            kernel.cnode_delete(INIT_THREAD_CNODE, 21, DEPTH),
+            Error::NoError
+        );
+        assert_eq!(_retype_(&mut kernel, ObjectType::Tcb, 0, 22, 1), Ok(()));
+    }
+
+    #[test]
+    fn test_copy_untyped_with_children() {
+        let mut kernel = with_untyped(16);
+        assert_eq!(
+            _retype_(&mut kernel, ObjectType::Endpoint, 0, 21, 1),
+            Ok(())
+        );
+        assert_eq!(
+            cnode_copy(
+                &mut kernel,
+                INIT_THREAD_CNODE,
+                22,
+                DEPTH,
+                INIT_THREAD_CNODE,
+                UNTYPED,
+                DEPTH,
+                CapRights::ALL
+            ),
+            Err(JustError::RevokeFirst)
+        );
+        // The endpoint is still derived from the untyped after deleting a copy of it.
+        assert_eq!(
+            cnode_copy(
+                &mut kernel,
+                INIT_THREAD_CNODE,
+                22,
+                DEPTH,
+                INIT_THREAD_CNODE,
+                21,
+                DEPTH,
+                CapRights::ALL
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            kernel.cnode_delete(INIT_THREAD_CNODE, 22, DEPTH),
+            Error::NoError
+        );
+        assert_eq!(
+            _retype_(&mut kernel, ObjectType::Endpoint, 0, 22, 1),
+            Ok(())
+        );
+        assert_eq!(
+            kernel.cnode_delete(INIT_THREAD_CNODE, 21, DEPTH),
+            Error::NoError
+        );
+        assert_eq!(
+            kernel.cnode_delete(INIT_THREAD_CNODE, 22, DEPTH),
+            Error::NoError
+        );
+        assert_eq!(
+            cnode_copy(
+                &mut kernel,
+                INIT_THREAD_CNODE,
+                22,
+                DEPTH,
+                INIT_THREAD_CNODE,
+                UNTYPED,
+                DEPTH,
+                CapRights::ALL
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_configure_buffer_not_a_frame() {
+        let mut kernel = MockKernel::new();
+        assert_eq!(
+            kernel.tcb_configure(
+                INIT_THREAD_TCB,
+                CAP_NULL,
+                INIT_THREAD_CNODE,
+                0,
+                INIT_THREAD_VSPACE,
+                0,
+                0x1000,
+                INIT_THREAD_TCB
+            ),
+            Error::IllegalOperation
+        );
+    }
+
+    #[test]
+    fn test_tcb_with_buffer() {
+        let mut kernel = with_untyped(16);
+        assert_eq!(kernel.insert(31, Cap::Frame), Error::NoError);
+        assert_eq!(_tcb_with_buffer_(&mut kernel, 0x1000), Ok(()));
+    }
+
+    #[test]
+    fn test_tcb_with_unaligned_buffer() {
+        let mut kernel = with_untyped(16);
+        assert_eq!(kernel.insert(31, Cap::Frame), Error::NoError);
+        assert_eq!(
+            _tcb_with_buffer_(&mut kernel, 0x1000 + 8),
+            Err(JustError::AlignmentError)
+        );
+    }
+
+    #[test]
+    fn test_tcb_with_missing_buffer() {
+        let mut kernel = with_untyped(16);
+        assert_eq!(
+            _tcb_with_buffer_(&mut kernel, 0x1000),
+            Err(JustError::IllegalOperation)
+        );
+    }
+}
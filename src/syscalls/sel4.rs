@@ -0,0 +1,121 @@
+use super::{CPtr, CapRights, Kernel, Word};
+use crate::intersection::Error;
+
+//
+// In libsel4 these are all `static inline`, so this expects to be linked against
+// a libsel4 built with out of line invocation stubs (or an equivalent C shim).
+//
+// They are declared as returning Error rather than int, which is the whole point of
+// the C compatible Error, but this does rely on the kernel never returning a code
+// outside of the range of seL4_Error.
+//
+extern "C" {
+    fn seL4_Untyped_Retype(
+        service: CPtr,
+        type_: Word,
+        size_bits: Word,
+        root: CPtr,
+        node_index: Word,
+        node_depth: Word,
+        node_offset: Word,
+        num_objects: Word,
+    ) -> Error;
+
+    fn seL4_CNode_Copy(
+        service: CPtr,
+        dest_index: Word,
+        dest_depth: u8,
+        src_root: CPtr,
+        src_index: Word,
+        src_depth: u8,
+        rights: CapRights,
+    ) -> Error;
+
+    fn seL4_CNode_Delete(service: CPtr, index: Word, depth: u8) -> Error;
+
+    fn seL4_TCB_Configure(
+        service: CPtr,
+        fault_ep: Word,
+        cspace_root: CPtr,
+        cspace_root_data: Word,
+        vspace_root: CPtr,
+        vspace_root_data: Word,
+        buffer: Word,
+        buffer_frame: CPtr,
+    ) -> Error;
+}
+
+pub struct Sel4;
+
+impl Kernel for Sel4 {
+    fn untyped_retype(
+        &mut self,
+        service: CPtr,
+        type_: Word,
+        size_bits: Word,
+        root: CPtr,
+        node_index: Word,
+        node_depth: Word,
+        node_offset: Word,
+        num_objects: Word,
+    ) -> Error {
+        unsafe {
+            seL4_Untyped_Retype(
+                service,
+                type_,
+                size_bits,
+                root,
+                node_index,
+                node_depth,
+                node_offset,
+                num_objects,
+            )
+        }
+    }
+
+    fn cnode_copy(
+        &mut self,
+        service: CPtr,
+        dest_index: Word,
+        dest_depth: u8,
+        src_root: CPtr,
+        src_index: Word,
+        src_depth: u8,
+        rights: CapRights,
+    ) -> Error {
+        unsafe {
+            seL4_CNode_Copy(
+                service, dest_index, dest_depth, src_root, src_index, src_depth, rights,
+            )
+        }
+    }
+
+    fn cnode_delete(&mut self, service: CPtr, index: Word, depth: u8) -> Error {
+        unsafe { seL4_CNode_Delete(service, index, depth) }
+    }
+
+    fn tcb_configure(
+        &mut self,
+        service: CPtr,
+        fault_ep: Word,
+        cspace_root: CPtr,
+        cspace_root_data: Word,
+        vspace_root: CPtr,
+        vspace_root_data: Word,
+        buffer: Word,
+        buffer_frame: CPtr,
+    ) -> Error {
+        unsafe {
+            seL4_TCB_Configure(
+                service,
+                fault_ep,
+                cspace_root,
+                cspace_root_data,
+                vspace_root,
+                vspace_root_data,
+                buffer,
+                buffer_frame,
+            )
+        }
+    }
+}
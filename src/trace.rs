@@ -0,0 +1,208 @@
+use core::fmt;
+use core::panic::Location;
+
+//
+// A return trace for intersection::Error, along the lines of zig's error return traces.
+//
+// Each `?` on an Error in a function returning Error records its location here,
+// Error itself is untouched, it is still just an i32.
+//
+// The buffer is a fixed size ring per thread, so nothing is allocated and the oldest
+// frames get overwritten. There is no telling where one error ends and the next begins,
+// so the last error is taken to be the most recent run of frames with the same error code.
+//
+// `?` in a function returning a core Result goes through Result's FromResidual,
+// so those hops are not recorded.
+//
+
+pub const CAPACITY: usize = 16;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Frame {
+    pub error_code: i32,
+    pub location: &'static Location<'static>,
+}
+
+struct Ring {
+    frames: [Option<Frame>; CAPACITY],
+    // Where the next frame goes.
+    next: usize,
+}
+
+#[thread_local]
+static mut RING: Ring = Ring {
+    frames: [None; CAPACITY],
+    next: 0,
+};
+
+pub(crate) fn record(error_code: i32, location: &'static Location<'static>) {
+    // Safety: RING is thread local, and nothing in here can re-enter.
+    let ring = unsafe { &mut RING };
+    ring.frames[ring.next] = Some(Frame {
+        error_code,
+        location,
+    });
+    ring.next = (ring.next + 1) % CAPACITY;
+}
+
+pub fn clear() {
+    // Safety: RING is thread local, and nothing in here can re-enter.
+    let ring = unsafe { &mut RING };
+    ring.frames = [None; CAPACITY];
+    ring.next = 0;
+}
+
+// The frames of the last error on this thread, oldest first.
+#[derive(Copy, Clone, Debug)]
+pub struct Trace {
+    frames: [Option<Frame>; CAPACITY],
+    len: usize,
+}
+
+impl Trace {
+    pub fn error_code(&self) -> Option<i32> {
+        self.frames[0].map(|frame| frame.error_code)
+    }
+
+    pub fn frames(&self) -> impl Iterator<Item = &Frame> {
+        self.frames[..self.len].iter().flatten()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // All CAPACITY frames are the same error, so the start of it may have been overwritten.
+    pub fn is_truncated(&self) -> bool {
+        self.len == CAPACITY
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.error_code() {
+            None => write!(f, "no error trace"),
+            Some(error_code) => {
+                write!(f, "error code: {}", error_code)?;
+                for frame in self.frames() {
+                    write!(f, "\n    at {}", frame.location)?;
+                }
+                if self.is_truncated() {
+                    write!(f, "\n    ...")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+pub fn last_error() -> Trace {
+    // Safety: RING is thread local, and nothing in here can re-enter.
+    let ring = unsafe { &RING };
+    let mut trace = Trace {
+        frames: [None; CAPACITY],
+        len: 0,
+    };
+    let newest = match ring.frames[(ring.next + CAPACITY - 1) % CAPACITY] {
+        Some(frame) => frame,
+        None => return trace,
+    };
+    // Walk backwards while the error code matches, then flip it around.
+    for i in 1..=CAPACITY {
+        match ring.frames[(ring.next + CAPACITY - i) % CAPACITY] {
+            Some(frame) if frame.error_code == newest.error_code => {
+                trace.frames[trace.len] = Some(frame);
+                trace.len += 1;
+            }
+            _ => break,
+        }
+    }
+    trace.frames[..trace.len].reverse();
+    trace
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intersection::Error;
+
+    fn _inner_() -> Error {
+        Error::RangeError?;
+        Error::NoError
+    }
+
+    fn _outer_() -> Error {
+        _inner_()?;
+        Error::NoError
+    }
+
+    fn _ok_() -> Error {
+        Error::NoError?;
+        Error::NoError
+    }
+
+    fn _recursive_(n: usize) -> Error {
+        if n == 0 {
+            return Error::DeleteFirst;
+        }
+        _recursive_(n - 1)?;
+        Error::NoError
+    }
+
+    #[test]
+    fn test_empty() {
+        clear();
+        assert_eq!(_ok_(), Error::NoError);
+        assert!(last_error().is_empty());
+        assert_eq!(last_error().error_code(), None);
+    }
+
+    #[test]
+    fn test_propagation_path() {
+        clear();
+        assert_eq!(_outer_(), Error::RangeError);
+        let trace = last_error();
+        assert_eq!(trace.error_code(), Some(Error::RangeError as i32));
+        assert_eq!(trace.len(), 2);
+        let mut frames = trace.frames();
+        let inner = frames.next().unwrap().location;
+        let outer = frames.next().unwrap().location;
+        assert_eq!(inner.file(), file!());
+        assert_eq!(outer.file(), file!());
+        // _inner_ is defined above _outer_.
+        assert!(inner.line() < outer.line());
+    }
+
+    #[test]
+    fn test_only_the_last_error() {
+        clear();
+        assert_eq!(_outer_(), Error::RangeError);
+        assert_eq!(_recursive_(3), Error::DeleteFirst);
+        let trace = last_error();
+        assert_eq!(trace.error_code(), Some(Error::DeleteFirst as i32));
+        assert_eq!(trace.len(), 3);
+        assert!(!trace.is_truncated());
+    }
+
+    #[test]
+    fn test_truncated() {
+        clear();
+        assert_eq!(_recursive_(CAPACITY + 4), Error::DeleteFirst);
+        let trace = last_error();
+        assert_eq!(trace.len(), CAPACITY);
+        assert!(trace.is_truncated());
+    }
+
+    #[test]
+    fn test_display() {
+        clear();
+        assert_eq!(_outer_(), Error::RangeError);
+        let trace = std::format!("{}", last_error());
+        assert!(trace.starts_with("error code: 4\n    at "));
+        assert_eq!(trace.matches(file!()).count(), 2);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::disjoint;
+use crate::hresult::HResult;
+use crate::names::Named;
+use crate::signed::SignedResult;
+use crate::{errno, intersection, repr, union};
+use core::result::Result as CoreResult;
+use ufmt::{uDebug, uWrite, Formatter};
+
+//
+// ufmt for the error enums, the names come from the names module's table
+// rather than from the Debug derive. error_domain! enums get theirs from the macro.
+//
+
+macro_rules! impl_udebug_by_name {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl uDebug for $ty {
+                fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> CoreResult<(), W::Error> {
+                    if cfg!(feature = "strip-names") {
+                        // As Display does, there are no names to write.
+                        f.write_str("error ")?;
+                        ufmt::uDisplay::fmt(&(*self as i64), f)
+                    } else {
+                        f.write_str(self.name())
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_udebug_by_name!(
+    union::Error,
+    intersection::Error,
+    intersection::JustError,
+    disjoint::NonError,
+    disjoint::Error,
+    errno::Errno,
+    errno::JustErrno,
+    repr::Error8,
+    repr::JustError8,
+    repr::ErrorWord,
+);
+
+// Whichever of NonError or Error it holds.
+impl uDebug for disjoint::Result {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> CoreResult<(), W::Error> {
+        match CoreResult::<disjoint::NonError, disjoint::Error>::from(*self) {
+            Ok(non_error) => {
+                f.write_str("Ok(")?;
+                non_error.fmt(f)?;
+            }
+            Err(error) => {
+                f.write_str("Err(")?;
+                error.fmt(f)?;
+            }
+        }
+        f.write_str(")")
+    }
+}
+
+// HResult(0x80000000), like its Debug.
+impl uDebug for HResult {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> CoreResult<(), W::Error> {
+        let mut hex = *b"0x00000000";
+        for (i, digit) in hex[2..].iter_mut().enumerate() {
+            let nibble = (self.0 >> (28 - 4 * i)) & 0xf;
+            *digit = b"0123456789abcdef"[nibble as usize];
+        }
+        f.write_str("HResult(")?;
+        // Safety: hex is all ASCII.
+        f.write_str(unsafe { core::str::from_utf8_unchecked(&hex) })?;
+        f.write_str(")")
+    }
+}
+
+impl<T> uDebug for SignedResult<T> {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> CoreResult<(), W::Error> {
+        match self.errno_code() {
+            Some(error_code) => ufmt::uwrite!(f, "errno: {}", error_code),
+            None => ufmt::uwrite!(f, "ok: {}", self.raw() as usize),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::convert::Infallible;
+    use std::string::String;
+
+    struct Buffer(String);
+
+    impl uWrite for Buffer {
+        type Error = Infallible;
+
+        fn write_str(&mut self, s: &str) -> CoreResult<(), Infallible> {
+            self.0.push_str(s);
+            Ok(())
+        }
+    }
+
+    fn _udebug_<T: uDebug>(it: T) -> String {
+        let mut buffer = Buffer(String::new());
+        ufmt::uwrite!(&mut buffer, "{:?}", it).unwrap();
+        buffer.0
+    }
+
+    #[cfg(not(feature = "strip-names"))]
+    #[test]
+    fn test_names() {
+        assert_eq!(_udebug_(intersection::Error::RangeError), "RangeError");
+        assert_eq!(_udebug_(union::Error::NoError), "NoError");
+        assert_eq!(_udebug_(errno::JustErrno::EINVAL), "EINVAL");
+        assert_eq!(_udebug_(repr::JustError8::DeleteFirst), "DeleteFirst");
+    }
+
+    #[cfg(not(feature = "strip-names"))]
+    #[test]
+    fn test_disjoint_result() {
+        let ok = disjoint::Result::from(Ok(disjoint::NonError::OkPartial));
+        assert_eq!(_udebug_(ok), "Ok(OkPartial)");
+        let err = disjoint::Result::from(Err(disjoint::Error::FailedLookup));
+        assert_eq!(_udebug_(err), "Err(FailedLookup)");
+    }
+
+    #[test]
+    fn test_domain() {
+        use crate::ffi::{FfiError, JustFfiError};
+        assert_eq!(_udebug_(FfiError::NoError), "NoError");
+        assert_eq!(_udebug_(JustFfiError::Internal), "Internal");
+        assert_eq!(_udebug_(JustFfiError::RangeError), "RangeError");
+    }
+
+    #[test]
+    fn test_hresult_and_signed() {
+        assert_eq!(
+            _udebug_(HResult::from(intersection::JustError::RangeError)),
+            format!("{:?}", HResult::from(intersection::JustError::RangeError))
+        );
+        assert_eq!(_udebug_(HResult::S_FALSE), "HResult(0x00000001)");
+        assert_eq!(_udebug_(SignedResult::<usize>::from_raw(-22)), "errno: 22");
+        assert_eq!(_udebug_(SignedResult::<usize>::from_raw(3)), "ok: 3");
+    }
+}
@@ -1,6 +1,4 @@
 use core::fmt::Debug;
-use core::num::NonZeroI32;
-use core::ops::{ControlFlow, FromResidual, Try};
 use core::result::Result as CoreResult;
 use strum::EnumCount;
 
@@ -16,38 +14,12 @@ impl From<CoreResult<(), Error>> for Error {
     }
 }
 
-impl From<Error> for CoreResult<(), Error> {
-    fn from(it: Error) -> Self {
-        match NonZeroI32::new(it as i32) {
-            None => CoreResult::Ok(()),
-            Some(_) => CoreResult::Err(it),
-        }
-    }
-}
-
-impl Try for Error {
-    type Output = ();
-    type Residual = Error;
-
-    fn branch(self) -> ControlFlow<Self::Residual, ()> {
-        match NonZeroI32::new(self as i32) {
-            None => ControlFlow::Continue(()),
-            Some(_) => ControlFlow::Break(self),
-        }
-    }
-
-    fn from_output((): ()) -> Self {
-        Error::NoError
-    }
-}
-
-impl FromResidual<Error> for Error {
-    fn from_residual(e: Error) -> Self {
-        e
-    }
-}
+// The From<Error> for CoreResult<(), Error>, Try, FromResidual and TryFrom<i32> impls.
+// `?` on a None is FailedLookup, it is usually a lookup which came up empty.
+crate::impl_union!(Error, i32, none = FailedLookup);
 
 #[derive(Copy, Clone, Debug, EnumCount, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(i32)]
 #[allow(clippy::enum_variant_names)]
 pub enum Error {
@@ -68,6 +40,12 @@ pub enum Error {
      */
 }
 
+// Compile time check that the discriminants are contiguous, TryFrom<i32> relies on it.
+const _: () = if Error::NotEnoughMemory as usize + 1 == Error::COUNT {
+} else {
+    panic!()
+};
+
 #[test]
 fn test_count() {
     assert_eq!(Error::COUNT as usize, Error::NotEnoughMemory as usize + 1)
@@ -149,6 +127,29 @@ fn _test_not_enough_memory_() -> Error {
     Error::NoError
 }
 
+// Idiomatic Rust helpers, which know nothing of Error.
+struct Exhausted;
+
+impl From<Exhausted> for Error {
+    fn from(_: Exhausted) -> Error {
+        Error::NotEnoughMemory
+    }
+}
+
+fn _allocate_(free: usize) -> CoreResult<usize, Exhausted> {
+    free.checked_sub(1).ok_or(Exhausted)
+}
+
+fn _test_core_result_(free: usize) -> Error {
+    let _ = _allocate_(free)?;
+    Error::NoError
+}
+
+fn _test_option_(slots: &[u8], wanted: u8) -> Error {
+    let _ = slots.iter().position(|slot| *slot == wanted)?;
+    Error::NoError
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -210,4 +211,59 @@ mod test {
     fn test_not_enough_memory() {
         assert_eq!(_test_not_enough_memory_(), Error::NotEnoughMemory);
     }
+
+    #[test]
+    fn test_question_mark_core_result() {
+        assert_eq!(_test_core_result_(1), Error::NoError);
+        assert_eq!(_test_core_result_(0), Error::NotEnoughMemory);
+    }
+
+    #[test]
+    fn test_question_mark_option() {
+        assert_eq!(_test_option_(&[1, 2], 2), Error::NoError);
+        assert_eq!(_test_option_(&[1, 2], 3), Error::FailedLookup);
+    }
+
+    // The combinators are const where they can be.
+    const _: () = if Error::NoError.is_ok() && Error::RangeError.is_err() {
+    } else {
+        panic!()
+    };
+
+    #[test]
+    fn test_combinators() {
+        assert!(Error::NoError.is_ok());
+        assert!(Error::RangeError.is_err());
+        assert_eq!(Error::NoError.ok(), Some(()));
+        assert_eq!(Error::RangeError.ok(), None);
+        assert_eq!(Error::NoError.err(), None);
+        assert_eq!(Error::RangeError.err(), Some(Error::RangeError));
+        assert_eq!(
+            Error::NoError.and_then(|| Error::DeleteFirst),
+            Error::DeleteFirst
+        );
+        assert_eq!(
+            Error::RangeError.and_then(|| Error::DeleteFirst),
+            Error::RangeError
+        );
+        assert_eq!(
+            Error::RangeError.or_else(|_| Error::NoError),
+            Error::NoError
+        );
+        assert_eq!(Error::RangeError.map_err(|error| error as i32), Err(4));
+        let mut seen = None;
+        assert_eq!(
+            Error::FailedLookup.inspect_err(|error| seen = Some(*error)),
+            Error::FailedLookup
+        );
+        assert_eq!(seen, Some(Error::FailedLookup));
+        Error::NoError.unwrap();
+        Error::NoError.expect("no error");
+    }
+
+    #[test]
+    #[should_panic(expected = "while retyping: RangeError")]
+    fn test_expect() {
+        Error::RangeError.expect("while retyping");
+    }
 }